@@ -42,6 +42,7 @@ fn main() -> Result<(), String> {
             out_kind: vec!["console".into()],
             roll_count: 0,
             time_format: Some("%H:%M:%S.%f".to_string()),
+            ..LogConfig::default()
         }
     );
 
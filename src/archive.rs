@@ -0,0 +1,502 @@
+//! Archive file names, see [crate::LogConfigBuilder::archive_pattern].
+//!
+//! Pattern tokens:
+//!
+//! ```bash
+//! %Y %m %d %H %M %S  # date and time of the archived period, or of the roll for size rotation
+//! %i                 # index, counting up from 0 for each name
+//! %h                 # hostname
+//! %p                 # pid
+//! %%                 # a literal %
+//! ```
+
+use crate::compression::Compression;
+use crate::rotation::RotationPeriod;
+use crate::{SimpleLogError, SimpleResult};
+use chrono::NaiveDateTime;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const PATTERN_EXPECT: &str =
+    "expect tokens %Y %m %d %H %M %S %i %h %p or %% in the file name, e.g. 'app-%Y%m%d-%i.log.gz'";
+
+/// Stripped from the end of a pattern, the extension of the configured compression is used.
+const COMPRESSION_EXTENSIONS: [&str; 3] = [".gz", ".zst", ".xz"];
+
+/// An archive of the log file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Archive {
+    pub(crate) path: PathBuf,
+    pub(crate) modified: SystemTime,
+    pub(crate) len: u64,
+}
+
+pub(crate) fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn parent(path: &Path) -> &Path {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Literal(String),
+    Year,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second,
+    Index,
+    Hostname,
+    Pid,
+}
+
+/// A pattern token resolved for matching file names.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    /// Exactly this many digits, any number of digits if `None`.
+    Digits(Option<usize>),
+    Index,
+}
+
+/// A parsed archive name pattern, e.g. `app-%Y%m%d-%i.log.gz`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ArchivePattern {
+    /// Relative to the directory of the log file, unless absolute.
+    dir: PathBuf,
+    tokens: Vec<Token>,
+    /// `tokens` with an index before the extension, used on collision when there is no `%i`.
+    collision: Option<Vec<Token>>,
+}
+
+impl ArchivePattern {
+    /// Parse `pattern`, replacing a compression extension it ends with by the one of `compression`.
+    pub(crate) fn new(pattern: &str, compression: Compression) -> SimpleResult<ArchivePattern> {
+        let err = |reason: &str| {
+            SimpleLogError::InvalidArchivePattern(format!(
+                "Invalid archive_pattern '{}', {},{}",
+                pattern, reason, PATTERN_EXPECT
+            ))
+        };
+        let path = Path::new(pattern);
+        let name = file_name(path);
+        if name.is_empty() {
+            return Err(err("missing file name"));
+        }
+        let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        if dir.to_string_lossy().contains('%') {
+            return Err(err("tokens are only supported in the file name"));
+        }
+        let name = COMPRESSION_EXTENSIONS
+            .iter()
+            .find_map(|ext| name.strip_suffix(ext))
+            .unwrap_or(&name);
+
+        let mut tokens = vec![];
+        let mut literal = String::new();
+        let mut chars = name.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                literal.push(c);
+                continue;
+            }
+            let token = match chars.next() {
+                Some('%') => {
+                    literal.push('%');
+                    continue;
+                }
+                Some('Y') => Token::Year,
+                Some('m') => Token::Month,
+                Some('d') => Token::Day,
+                Some('H') => Token::Hour,
+                Some('M') => Token::Minute,
+                Some('S') => Token::Second,
+                Some('i') => Token::Index,
+                Some('h') => Token::Hostname,
+                Some('p') => Token::Pid,
+                Some(c) => return Err(err(&format!("unknown token '%{}'", c))),
+                None => return Err(err("dangling '%'")),
+            };
+            if !literal.is_empty() {
+                tokens.push(Token::Literal(std::mem::take(&mut literal)));
+            }
+            tokens.push(token);
+        }
+        if !literal.is_empty() {
+            tokens.push(Token::Literal(literal));
+        }
+
+        // `app.log` becomes `app.log.{i}`, then the extension is added to both
+        let ext = Token::Literal(compression.extension().to_string());
+        let collision = (!tokens.contains(&Token::Index)).then(|| {
+            let mut collision = tokens.clone();
+            collision.extend([Token::Literal(".".to_string()), Token::Index, ext.clone()]);
+            collision
+        });
+        tokens.push(ext);
+
+        Ok(ArchivePattern {
+            dir,
+            tokens,
+            collision,
+        })
+    }
+
+    fn dir(&self, path: &Path) -> PathBuf {
+        parent(path).join(&self.dir)
+    }
+
+    /// Resolve `tokens` for `time` and this process, or for any time and pid if `None`.
+    fn parts(tokens: &[Token], time: Option<NaiveDateTime>) -> Vec<Part> {
+        let digits = |format: &str, width: usize| match time {
+            Some(time) => Part::Literal(time.format(format).to_string()),
+            None => Part::Digits(Some(width)),
+        };
+        tokens
+            .iter()
+            .map(|token| match token {
+                Token::Literal(literal) => Part::Literal(literal.clone()),
+                Token::Year => digits("%Y", 4),
+                Token::Month => digits("%m", 2),
+                Token::Day => digits("%d", 2),
+                Token::Hour => digits("%H", 2),
+                Token::Minute => digits("%M", 2),
+                Token::Second => digits("%S", 2),
+                Token::Index => Part::Index,
+                Token::Hostname => Part::Literal(hostname().replace('/', "_")),
+                Token::Pid => match time {
+                    Some(_) => Part::Literal(std::process::id().to_string()),
+                    None => Part::Digits(None),
+                },
+            })
+            .collect()
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        std::iter::once(&self.tokens)
+            .chain(&self.collision)
+            .any(|tokens| match_parts(&ArchivePattern::parts(tokens, None), name, None).is_some())
+    }
+
+    /// The first free name for an archive of `time`.
+    ///
+    /// With `%i` the index counts up from the highest existing one,
+    /// so the order is kept when older archives are deleted.
+    fn next(&self, path: &Path, time: NaiveDateTime) -> io::Result<PathBuf> {
+        let dir = self.dir(path);
+        fs::create_dir_all(&dir)?;
+        let tokens = match &self.collision {
+            Some(collision) => {
+                let name = render(&ArchivePattern::parts(&self.tokens, Some(time)), 0);
+                if !dir.join(&name).exists() {
+                    return Ok(dir.join(name));
+                }
+                collision
+            }
+            None => &self.tokens,
+        };
+        let parts = ArchivePattern::parts(tokens, Some(time));
+        let mut index = 0;
+        for entry in fs::read_dir(&dir)? {
+            let name = entry?.file_name().to_string_lossy().to_string();
+            if let Some(Some(i)) = match_parts(&parts, &name, None) {
+                index = index.max(i + 1);
+            }
+        }
+        if self.collision.is_some() {
+            index = index.max(1);
+        }
+        loop {
+            let archive = dir.join(render(&parts, index));
+            if !archive.exists() {
+                return Ok(archive);
+            }
+            index += 1;
+        }
+    }
+}
+
+/// Match `name` against `parts`, returning the captured index.
+fn match_parts(parts: &[Part], name: &str, index: Option<u64>) -> Option<Option<u64>> {
+    let digits = name.bytes().take_while(u8::is_ascii_digit).count();
+    match parts.split_first() {
+        None => name.is_empty().then_some(index),
+        Some((Part::Literal(literal), rest)) => {
+            match_parts(rest, name.strip_prefix(literal.as_str())?, index)
+        }
+        Some((Part::Digits(Some(width)), rest)) if digits >= *width => {
+            match_parts(rest, &name[*width..], index)
+        }
+        Some((Part::Digits(Some(_)), _)) => None,
+        Some((Part::Digits(None), rest)) => (1..=digits)
+            .rev()
+            .find_map(|n| match_parts(rest, &name[n..], index)),
+        Some((Part::Index, rest)) => (1..=digits)
+            .rev()
+            .find_map(|n| match_parts(rest, &name[n..], name[..n].parse().ok())),
+    }
+}
+
+/// Render resolved `parts`, every token but the index must be resolved.
+fn render(parts: &[Part], index: u64) -> String {
+    parts
+        .iter()
+        .map(|part| match part {
+            Part::Literal(literal) => literal.clone(),
+            Part::Index => index.to_string(),
+            Part::Digits(_) => String::new(),
+        })
+        .collect()
+}
+
+/// The host name, `localhost` if it is unknown.
+///
+/// Taken from the `HOSTNAME` or `COMPUTERNAME` variable, else from the kernel or `/etc/hostname`.
+pub(crate) fn hostname() -> String {
+    let vars = ["HOSTNAME", "COMPUTERNAME"]
+        .into_iter()
+        .filter_map(|name| std::env::var(name).ok());
+    let files = ["/proc/sys/kernel/hostname", "/etc/hostname"]
+        .into_iter()
+        .filter_map(|path| fs::read_to_string(path).ok());
+    vars.chain(files)
+        .map(|name| name.trim().to_string())
+        .find(|name| !name.is_empty())
+        .unwrap_or_else(|| "localhost".to_string())
+}
+
+/// How rolled files are named.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ArchiveNames {
+    pattern: ArchivePattern,
+    /// Renumber `{path}.{index}` on each roll, the default for size rotation.
+    shift: bool,
+}
+
+impl ArchiveNames {
+    /// Name archives by `archive_pattern`, or by default:
+    /// `{path}.{index}.gz` renumbered on each roll for size rotation,
+    /// `{path}.{label}.gz` for time rotation, see [RotationPeriod].
+    pub(crate) fn new(
+        path: &Path,
+        archive_pattern: Option<&str>,
+        period: Option<RotationPeriod>,
+        compression: Compression,
+    ) -> SimpleResult<ArchiveNames> {
+        if let Some(pattern) = archive_pattern {
+            return Ok(ArchiveNames {
+                pattern: ArchivePattern::new(pattern, compression)?,
+                shift: false,
+            });
+        }
+        let name = file_name(path).replace('%', "%%");
+        let pattern = match period {
+            Some(period) => format!("{}.{}", name, period.label_format()),
+            None => format!("{}.%i", name),
+        };
+        Ok(ArchiveNames {
+            pattern: ArchivePattern::new(&pattern, compression)?,
+            shift: period.is_none(),
+        })
+    }
+
+    /// The archives of `path`, newest first.
+    pub(crate) fn list(&self, path: &Path) -> io::Result<Vec<Archive>> {
+        let entries = match fs::read_dir(self.pattern.dir(path)) {
+            Ok(entries) => entries,
+            // nothing archived yet
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e),
+        };
+        let mut archives = vec![];
+        for entry in entries {
+            let entry = entry?;
+            if self.pattern.matches(&entry.file_name().to_string_lossy()) {
+                let metadata = entry.metadata()?;
+                archives.push(Archive {
+                    path: entry.path(),
+                    modified: metadata.modified()?,
+                    len: metadata.len(),
+                });
+            }
+        }
+        archives.sort_by(|a, b| {
+            b.modified
+                .cmp(&a.modified)
+                .then_with(|| b.path.cmp(&a.path))
+        });
+        Ok(archives)
+    }
+
+    /// The name for the next archive of `path`, written in the period starting at `time`.
+    ///
+    /// `None` when `max_count` is 0 and the rolled file is dropped.
+    pub(crate) fn next(
+        &self,
+        path: &Path,
+        time: NaiveDateTime,
+        max_count: usize,
+    ) -> io::Result<Option<PathBuf>> {
+        match self.shift {
+            true => self.shift_archives(path, time, max_count),
+            false => self.pattern.next(path, time).map(Some),
+        }
+    }
+
+    /// Move `{path}.{i}` to `{path}.{i + 1}`, dropping the archives past `count`,
+    /// and return the free `{path}.0`.
+    fn shift_archives(
+        &self,
+        path: &Path,
+        time: NaiveDateTime,
+        count: usize,
+    ) -> io::Result<Option<PathBuf>> {
+        let parts = ArchivePattern::parts(&self.pattern.tokens, Some(time));
+        let indexed = |i: usize| path.with_file_name(render(&parts, i as u64));
+        if count == 0 {
+            return Ok(None);
+        }
+        let last = indexed(count - 1);
+        if last.exists() {
+            fs::remove_file(last)?;
+        }
+        for i in (0..count - 1).rev() {
+            let src = indexed(i);
+            if src.exists() {
+                fs::rename(src, indexed(i + 1))?;
+            }
+        }
+        Ok(Some(indexed(0)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn time() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 3, 1)
+            .unwrap()
+            .and_hms_opt(13, 5, 9)
+            .unwrap()
+    }
+
+    const GZIP: Compression = Compression::Gzip { level: 6 };
+
+    fn pattern(s: &str) -> ArchivePattern {
+        ArchivePattern::new(s, GZIP).unwrap()
+    }
+
+    fn names(s: &str) -> ArchiveNames {
+        ArchiveNames::new(Path::new("app.log"), Some(s), None, GZIP).unwrap()
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_parse_pattern() {
+        let p = pattern("archive/app-%Y%m%d-%i.log.gz");
+        assert_eq!(p.dir, PathBuf::from("archive"));
+        assert_eq!(p.collision, None);
+        assert_eq!(
+            render(&ArchivePattern::parts(&p.tokens, Some(time())), 3),
+            "app-20240301-3.log.gz"
+        );
+
+        let p = pattern("app.%H%M%S.100%%.log");
+        assert_eq!(
+            render(&ArchivePattern::parts(&p.tokens, Some(time())), 0),
+            "app.130509.100%.log.gz"
+        );
+        let pid = std::process::id();
+        let p = pattern("app-%h-%p.log.gz");
+        assert_eq!(
+            render(&ArchivePattern::parts(&p.tokens, Some(time())), 0),
+            format!("app-{}-{}.log.gz", hostname(), pid)
+        );
+
+        let p = ArchivePattern::new("app-%i.log.gz", Compression::Zstd { level: 3 }).unwrap();
+        assert_eq!(
+            render(&ArchivePattern::parts(&p.tokens, Some(time())), 0),
+            "app-0.log.zst"
+        );
+        let p = ArchivePattern::new("app-%i.log.gz", Compression::None).unwrap();
+        assert_eq!(
+            render(&ArchivePattern::parts(&p.tokens, Some(time())), 0),
+            "app-0.log"
+        );
+
+        for invalid in ["", "app-%x.log", "app-%", "%Y/app.log"] {
+            assert!(ArchivePattern::new(invalid, GZIP).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_pattern_matches() {
+        let p = pattern("app-%Y%m%d-%i.log.gz");
+        assert!(p.matches("app-20240301-0.log.gz"));
+        assert!(p.matches("app-20240301-12.log.gz"));
+        assert!(!p.matches("app-2024031-0.log.gz"));
+        assert!(!p.matches("app-20240301-.log.gz"));
+        assert!(!p.matches("app.log"));
+
+        let p = pattern("app-%Y%m%d.log.gz");
+        assert!(p.matches("app-20240301.log.gz"));
+        assert!(p.matches("app-20240301.log.2.gz"));
+
+        let p = pattern("app-%p.log.gz");
+        assert!(p.matches("app-1.log.gz"));
+    }
+
+    #[test]
+    fn test_pattern_next_index() {
+        let dir = test_dir("simple_log_test_pattern_index");
+        let path = dir.join("app.log");
+        let names = names("app-%Y%m%d-%i.log.gz");
+
+        let first = names.next(&path, time(), 10).unwrap().unwrap();
+        assert_eq!(file_name(&first), "app-20240301-0.log.gz");
+        fs::write(&first, "").unwrap();
+        fs::write(dir.join("app-20240301-7.log.gz"), "").unwrap();
+        fs::write(dir.join("app-20240302-9.log.gz"), "").unwrap();
+
+        // counts up from the highest index of the same day
+        let next = names.next(&path, time(), 10).unwrap().unwrap();
+        assert_eq!(file_name(&next), "app-20240301-8.log.gz");
+        assert_eq!(names.list(&path).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_pattern_next_collision() {
+        let dir = test_dir("simple_log_test_pattern_collision");
+        let path = dir.join("app.log");
+        let names = names("old/app-%Y%m%d.log");
+
+        for expected in [
+            "app-20240301.log.gz",
+            "app-20240301.log.1.gz",
+            "app-20240301.log.2.gz",
+        ] {
+            let next = names.next(&path, time(), 10).unwrap().unwrap();
+            assert_eq!(next, dir.join("old").join(expected));
+            fs::write(&next, "").unwrap();
+        }
+        assert_eq!(names.list(&path).unwrap().len(), 3);
+    }
+}
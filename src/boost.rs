@@ -0,0 +1,246 @@
+//! Temporary level boosts that revert by themselves.
+
+use crate::inner::{get_boosts, modify_boosts};
+use crate::level::{parse_target_name, target_level};
+use crate::{LogConfig, SimpleResult, TargetLevel};
+use log::LevelFilter;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+static NEXT_BOOST_ID: AtomicU64 = AtomicU64::new(1);
+
+/// An active boost created by [boost_level].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LevelBoost {
+    id: u64,
+    target: String,
+    level: LevelFilter,
+    expires_at: SystemTime,
+}
+
+impl LevelBoost {
+    pub fn get_target(&self) -> &str {
+        &self.target
+    }
+
+    pub fn get_level(&self) -> LevelFilter {
+        self.level
+    }
+
+    pub fn get_expires_at(&self) -> SystemTime {
+        self.expires_at
+    }
+}
+
+/// The active boosts of a [LogConfig] returned by [crate::get_log_conf]. They are state
+/// of the running logger, not config, so they are equal to any other boosts.
+///
+/// Read them with [LogConfig::get_boosts].
+#[doc(hidden)]
+#[derive(Debug, Clone, Default)]
+pub struct ActiveBoosts(pub(crate) Vec<LevelBoost>);
+
+impl PartialEq for ActiveBoosts {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for ActiveBoosts {}
+
+impl Deref for ActiveBoosts {
+    type Target = [LevelBoost];
+
+    fn deref(&self) -> &[LevelBoost] {
+        &self.0
+    }
+}
+
+impl LogConfig {
+    /// The boosts active when the config was taken by [crate::get_log_conf].
+    ///
+    /// They are ignored by [crate::new] and [crate::update_log_conf].
+    pub fn get_boosts(&self) -> &[LevelBoost] {
+        &self.boosts
+    }
+
+    /// The level of `target` without boosts: the most specific target level, or the root level.
+    fn base_level(&self, target: &str) -> LevelFilter {
+        self.level_in(&self.level.1, target)
+    }
+
+    /// The most specific level of `target` in `targets`, or the root level.
+    pub(crate) fn level_in(&self, targets: &[TargetLevel], target: &str) -> LevelFilter {
+        target_level(targets, target).unwrap_or(self.level.0)
+    }
+
+    /// Target levels with `boosts` applied, the most verbose level wins.
+    pub(crate) fn effective_target_levels(&self, boosts: &[LevelBoost]) -> Vec<TargetLevel> {
+        let mut targets = self.level.1.clone();
+        for boost in boosts {
+            let level = self.base_level(&boost.target).max(boost.level);
+            match targets.iter_mut().find(|t| t.name == boost.target) {
+                Some(target) => target.level = target.level.max(level),
+                None => targets.push((&boost.target, level).into()),
+            }
+        }
+        targets
+    }
+}
+
+/// The active boosts of [boost_level], the same as [LogConfig::get_boosts] of
+/// [crate::get_log_conf].
+pub fn active_boosts() -> SimpleResult<Vec<LevelBoost>> {
+    get_boosts()
+}
+
+/// Reverts its boost when dropped, see [boost_level].
+#[must_use = "the boost is reverted when the guard is dropped"]
+pub struct LevelBoostGuard {
+    id: u64,
+    cancel: Option<Sender<()>>,
+}
+
+impl LevelBoostGuard {
+    /// Keep the boost until it expires, even after the guard is gone.
+    pub fn detach(mut self) {
+        self.cancel.take();
+    }
+}
+
+impl Drop for LevelBoostGuard {
+    fn drop(&mut self) {
+        if let Some(cancel) = self.cancel.take() {
+            let _ = remove_boost(self.id);
+            let _ = cancel.send(());
+        }
+    }
+}
+
+fn remove_boost(id: u64) -> SimpleResult<()> {
+    modify_boosts(|boosts| boosts.retain(|boost| boost.id != id))
+}
+
+/// Raise `target` to `level` for `duration`.
+///
+/// The boost is reverted when the returned guard is dropped or when `duration` has passed,
+/// whichever comes first. Overlapping boosts stack: the most verbose active boost wins,
+/// and the target goes back to its configured level once all of them are gone.
+/// [crate::get_log_conf] lists them with [LogConfig::get_boosts].
+///
+/// # Examples
+///
+/// ```rust
+/// use log::LevelFilter;
+/// use std::time::Duration;
+///
+/// fn main() -> Result<(), String> {
+///     simple_log::console("info,app=warn")?;
+///
+///     let debug = simple_log::boost_level("app::db", LevelFilter::Debug, Duration::from_secs(60))?;
+///     let trace = simple_log::boost_level("app::db", LevelFilter::Trace, Duration::from_millis(50))?;
+///     assert_eq!(simple_log::active_boosts()?.len(), 2);
+///     assert_eq!(simple_log::get_log_conf()?.get_boosts().len(), 2);
+///     assert!(log::log_enabled!(target: "app::db", log::Level::Trace));
+///
+///     // the trace boost expires, the debug boost is still active
+///     std::thread::sleep(Duration::from_millis(200));
+///     assert!(!log::log_enabled!(target: "app::db", log::Level::Trace));
+///     assert!(log::log_enabled!(target: "app::db", log::Level::Debug));
+///     drop(trace);
+///
+///     drop(debug);
+///     assert!(!log::log_enabled!(target: "app::db", log::Level::Info));
+///     assert!(simple_log::active_boosts()?.is_empty());
+///     Ok(())
+/// }
+/// ```
+pub fn boost_level<S: AsRef<str>>(
+    target: S,
+    level: LevelFilter,
+    duration: Duration,
+) -> SimpleResult<LevelBoostGuard> {
+    let target = parse_target_name(target.as_ref())?.to_string();
+    let id = NEXT_BOOST_ID.fetch_add(1, Ordering::Relaxed);
+    let deadline = Instant::now() + duration;
+    modify_boosts(|boosts| {
+        boosts.push(LevelBoost {
+            id,
+            target,
+            level,
+            expires_at: SystemTime::now() + duration,
+        })
+    })?;
+
+    let (cancel, cancelled) = mpsc::channel();
+    thread::spawn(move || match cancelled.recv_timeout(duration) {
+        // the guard already reverted the boost
+        Ok(()) => {}
+        // detached, wait for the deadline
+        Err(RecvTimeoutError::Disconnected) => {
+            thread::sleep(deadline.saturating_duration_since(Instant::now()));
+            let _ = remove_boost(id);
+        }
+        Err(RecvTimeoutError::Timeout) => {
+            let _ = remove_boost(id);
+        }
+    });
+
+    Ok(LevelBoostGuard {
+        id,
+        cancel: Some(cancel),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LogConfigBuilder;
+
+    fn boost(target: &str, level: LevelFilter) -> LevelBoost {
+        LevelBoost {
+            id: 0,
+            target: target.to_string(),
+            level,
+            expires_at: SystemTime::now(),
+        }
+    }
+
+    #[test]
+    fn test_effective_target_levels() {
+        let config = LogConfigBuilder::builder()
+            .level("info,app=warn,app::db=error")
+            .unwrap()
+            .build();
+        let boosts = [
+            boost("app", LevelFilter::Debug),
+            boost("app::db", LevelFilter::Trace),
+            boost("app::http", LevelFilter::Info),
+            boost("app::http", LevelFilter::Debug),
+            boost("hyper", LevelFilter::Error),
+        ];
+        assert_eq!(
+            config.effective_target_levels(&boosts),
+            vec![
+                ("app", LevelFilter::Debug).into(),
+                ("app::db", LevelFilter::Trace).into(),
+                ("app::http", LevelFilter::Debug).into(),
+                ("hyper", LevelFilter::Info).into(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_base_level() {
+        let config = LogConfigBuilder::builder()
+            .level("info,app=warn,app::db=error")
+            .unwrap()
+            .build();
+        assert_eq!(config.base_level("app::db::pool"), LevelFilter::Error);
+        assert_eq!(config.base_level("app::dbx"), LevelFilter::Warn);
+        assert_eq!(config.base_level("application"), LevelFilter::Info);
+    }
+}
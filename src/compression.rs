@@ -0,0 +1,255 @@
+//! Archive compression.
+//!
+//! [Compression] is configured with a string, zstd and xz need their cargo feature:
+//!
+//! ```bash
+//! compression = "gzip"     # the default, level 6, always available
+//! compression = "gzip:9"
+//! compression = "zstd:19"  # level 3 if omitted, feature `zstd`
+//! compression = "xz"       # feature `xz`
+//! compression = "none"     # keep the rolled file as is
+//! ```
+
+use crate::{SimpleLogError, SimpleResult};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+const COMPRESSION_EXPECT: &str =
+    "expect compression 'none', 'gzip', 'gzip:<0-9>', 'zstd', 'zstd:<1-22>' or 'xz'";
+
+const DEFAULT_GZIP_LEVEL: u32 = 6;
+const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
+/// How archives are compressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Keep the rolled file uncompressed.
+    None,
+    /// `.gz`, level 0-9.
+    Gzip { level: u32 },
+    /// `.zst`, level 1-22.
+    Zstd { level: i32 },
+    /// `.xz`.
+    Xz,
+}
+
+impl Default for Compression {
+    /// Gzip, as before.
+    fn default() -> Self {
+        Compression::Gzip {
+            level: DEFAULT_GZIP_LEVEL,
+        }
+    }
+}
+
+impl Compression {
+    /// The archive file extension, e.g. `.gz`.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::None => "",
+            Compression::Gzip { .. } => ".gz",
+            Compression::Zstd { .. } => ".zst",
+            Compression::Xz => ".xz",
+        }
+    }
+
+    /// Fails if the cargo feature of this format is not enabled.
+    pub(crate) fn check_enabled(&self) -> SimpleResult<()> {
+        let feature = match self {
+            Compression::None | Compression::Gzip { .. } => return Ok(()),
+            Compression::Zstd { .. } if cfg!(feature = "zstd") => return Ok(()),
+            Compression::Xz if cfg!(feature = "xz") => return Ok(()),
+            Compression::Zstd { .. } => "zstd",
+            Compression::Xz => "xz",
+        };
+        Err(SimpleLogError::Config(format!(
+            "compression '{}' requires the `{}` feature of simple-log",
+            self, feature
+        )))
+    }
+
+    /// Move `src` to `dst`, compressing it on the way.
+    pub(crate) fn compress(&self, src: &Path, dst: &Path) -> io::Result<()> {
+        match self {
+            Compression::None => fs::rename(src, dst),
+            Compression::Gzip { level } => {
+                let mut output = flate2::write::GzEncoder::new(
+                    fs::File::create(dst)?,
+                    flate2::Compression::new(*level),
+                );
+                io::copy(&mut io::BufReader::new(fs::File::open(src)?), &mut output)?;
+                output.finish()?;
+                fs::remove_file(src)
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd { level } => {
+                zstd::stream::copy_encode(fs::File::open(src)?, fs::File::create(dst)?, *level)?;
+                fs::remove_file(src)
+            }
+            #[cfg(feature = "xz")]
+            Compression::Xz => {
+                let mut output = xz2::write::XzEncoder::new(fs::File::create(dst)?, 6);
+                io::copy(&mut io::BufReader::new(fs::File::open(src)?), &mut output)?;
+                output.finish()?;
+                fs::remove_file(src)
+            }
+            #[allow(unreachable_patterns)]
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("compression '{}' is not enabled", self),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Compression::None => f.write_str("none"),
+            Compression::Gzip { level } => write!(f, "gzip:{}", level),
+            Compression::Zstd { level } => write!(f, "zstd:{}", level),
+            Compression::Xz => f.write_str("xz"),
+        }
+    }
+}
+
+impl FromStr for Compression {
+    type Err = SimpleLogError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || {
+            SimpleLogError::InvalidCompression(format!(
+                "Invalid compression '{}',{}",
+                s, COMPRESSION_EXPECT
+            ))
+        };
+        let lower = s.trim().to_ascii_lowercase();
+        let (name, level) = match lower.split_once(':') {
+            Some((name, level)) => (name.trim(), Some(level.trim())),
+            None => (lower.as_str(), None),
+        };
+        match (name, level) {
+            ("none", None) => Ok(Compression::None),
+            ("xz", None) => Ok(Compression::Xz),
+            ("gzip" | "gz", None) => Ok(Compression::Gzip {
+                level: DEFAULT_GZIP_LEVEL,
+            }),
+            ("gzip" | "gz", Some(level)) => match level.parse() {
+                Ok(level @ 0..=9) => Ok(Compression::Gzip { level }),
+                _ => Err(err()),
+            },
+            ("zstd" | "zst", None) => Ok(Compression::Zstd {
+                level: DEFAULT_ZSTD_LEVEL,
+            }),
+            ("zstd" | "zst", Some(level)) => match level.parse() {
+                Ok(level @ 1..=22) => Ok(Compression::Zstd { level }),
+                _ => Err(err()),
+            },
+            _ => Err(err()),
+        }
+    }
+}
+
+impl Serialize for Compression {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Compression {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_compression_serde() {
+        for (input, compression) in [
+            ("none", Compression::None),
+            ("gzip:9", Compression::Gzip { level: 9 }),
+            ("zstd:19", Compression::Zstd { level: 19 }),
+            ("xz", Compression::Xz),
+        ] {
+            assert_eq!(input.parse::<Compression>().unwrap(), compression);
+            assert_eq!(compression.to_string(), input);
+        }
+        assert_eq!(
+            " GZIP ".parse::<Compression>().unwrap(),
+            Compression::Gzip { level: 6 }
+        );
+        assert_eq!(
+            "zstd".parse::<Compression>().unwrap(),
+            Compression::Zstd { level: 3 }
+        );
+        for input in ["", "gzip:10", "zstd:0", "xz:6", "lz4", "none:1"] {
+            assert!(input.parse::<Compression>().is_err(), "{}", input);
+        }
+    }
+
+    fn round_trip(compression: Compression, decode: fn(fs::File) -> Box<dyn Read>) {
+        let dir = std::env::temp_dir().join("simple_log_test_compress");
+        fs::create_dir_all(&dir).unwrap();
+        let src = dir.join(format!("app.{}.log", compression));
+        let dst = dir.join(format!(
+            "app.{}.log.0{}",
+            compression,
+            compression.extension()
+        ));
+        let content = "2024-03-01 13:05:09 [INFO] hello\n".repeat(100);
+        fs::write(&src, &content).unwrap();
+
+        compression.check_enabled().unwrap();
+        compression.compress(&src, &dst).unwrap();
+        assert!(!src.exists());
+        let mut decoded = String::new();
+        decode(fs::File::open(&dst).unwrap())
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, content);
+    }
+
+    #[test]
+    fn test_compress_none() {
+        round_trip(Compression::None, |file| Box::new(file));
+    }
+
+    #[test]
+    fn test_compress_gzip() {
+        round_trip(Compression::Gzip { level: 9 }, |file| {
+            Box::new(flate2::read::GzDecoder::new(file))
+        });
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_compress_zstd() {
+        round_trip(Compression::Zstd { level: 19 }, |file| {
+            Box::new(zstd::stream::read::Decoder::new(file).unwrap())
+        });
+    }
+
+    #[cfg(feature = "xz")]
+    #[test]
+    fn test_compress_xz() {
+        round_trip(Compression::Xz, |file| {
+            Box::new(xz2::read::XzDecoder::new(file))
+        });
+    }
+
+    #[cfg(not(feature = "zstd"))]
+    #[test]
+    fn test_compression_not_enabled() {
+        let err = Compression::Zstd { level: 3 }.check_enabled().unwrap_err();
+        assert!(err.to_string().contains("requires the `zstd` feature"));
+    }
+}
@@ -0,0 +1,247 @@
+//! Load [LogConfig] from a TOML, YAML or JSON file.
+//!
+//! Each format is behind its cargo feature: `toml`, `yaml` and `json`.
+
+use crate::inner::init_default_log;
+use crate::{LogConfig, SimpleLogError, SimpleResult};
+use serde::de::DeserializeOwned;
+use std::path::Path;
+
+/// The config file formats understood by [LogConfig::from_path].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// Pick the format by file extension: `.toml`, `.yaml`/`.yml` or `.json`.
+    pub fn from_extension<P: AsRef<Path>>(path: P) -> Option<ConfigFormat> {
+        let ext = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "toml" => Some(ConfigFormat::Toml),
+            "yaml" | "yml" => Some(ConfigFormat::Yaml),
+            "json" => Some(ConfigFormat::Json),
+            _ => None,
+        }
+    }
+
+    /// Guess the format from the first meaningful line of the content.
+    ///
+    /// ```rust
+    /// use simple_log::ConfigFormat;
+    ///
+    /// assert_eq!(ConfigFormat::detect(r#"{"level":"debug"}"#), Some(ConfigFormat::Json));
+    /// assert_eq!(ConfigFormat::detect("# comment\nlevel = \"debug\""), Some(ConfigFormat::Toml));
+    /// assert_eq!(ConfigFormat::detect("[log_config]\nlevel = \"debug\""), Some(ConfigFormat::Toml));
+    /// assert_eq!(ConfigFormat::detect("level: debug"), Some(ConfigFormat::Yaml));
+    /// assert_eq!(ConfigFormat::detect(""), None);
+    /// ```
+    pub fn detect(content: &str) -> Option<ConfigFormat> {
+        let line = content
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'))?;
+        if line.starts_with('{') {
+            return Some(ConfigFormat::Json);
+        }
+        if line.starts_with('[') {
+            return Some(ConfigFormat::Toml);
+        }
+        match (line.find('='), line.find(':')) {
+            (Some(eq), Some(colon)) if eq < colon => Some(ConfigFormat::Toml),
+            (Some(_), None) => Some(ConfigFormat::Toml),
+            (_, Some(_)) => Some(ConfigFormat::Yaml),
+            (None, None) => None,
+        }
+    }
+
+    /// Deserialize `content` read from `path` with this format.
+    #[cfg_attr(
+        not(any(feature = "toml", feature = "yaml", feature = "json")),
+        allow(unused_variables)
+    )]
+    pub fn parse<T: DeserializeOwned>(self, path: &Path, content: &str) -> SimpleResult<T> {
+        let parse_err = |line: Option<usize>, message: String| SimpleLogError::ParseConfig {
+            path: path.to_path_buf(),
+            line,
+            message,
+        };
+        match self {
+            #[cfg(feature = "toml")]
+            ConfigFormat::Toml => toml::from_str(content).map_err(|e| {
+                let line = e
+                    .span()
+                    .map(|span| content[..span.start].matches('\n').count() + 1);
+                parse_err(line, e.message().to_string())
+            }),
+            #[cfg(feature = "yaml")]
+            ConfigFormat::Yaml => serde_yaml::from_str(content)
+                .map_err(|e| parse_err(e.location().map(|l| l.line()), e.to_string())),
+            #[cfg(feature = "json")]
+            ConfigFormat::Json => {
+                serde_json::from_str(content).map_err(|e| parse_err(Some(e.line()), e.to_string()))
+            }
+            #[allow(unreachable_patterns)]
+            format => Err(parse_err(
+                None,
+                format!(
+                    "{:?} config support is not enabled, enable the `{}` feature of simple-log",
+                    format,
+                    format.feature()
+                ),
+            )),
+        }
+    }
+
+    fn feature(self) -> &'static str {
+        match self {
+            ConfigFormat::Toml => "toml",
+            ConfigFormat::Yaml => "yaml",
+            ConfigFormat::Json => "json",
+        }
+    }
+}
+
+/// Read `path` and deserialize it, picking the format by extension or content.
+pub(crate) fn read_config<T: DeserializeOwned>(path: &Path) -> SimpleResult<T> {
+    let content = std::fs::read_to_string(path).map_err(|source| SimpleLogError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let format = ConfigFormat::from_extension(path)
+        .or_else(|| ConfigFormat::detect(&content))
+        .ok_or_else(|| SimpleLogError::ParseConfig {
+            path: path.to_path_buf(),
+            line: None,
+            message: "unknown config format, expect toml, yaml or json".to_string(),
+        })?;
+    format.parse(path, &content)
+}
+
+/// Load [LogConfig] from `path` and apply it with [crate::update_log_conf].
+pub(crate) fn reload_from_path(path: &Path) -> SimpleResult<LogConfig> {
+    let mut config: LogConfig = read_config(path)?;
+    init_default_log(&mut config);
+    crate::update_log_conf(config)
+}
+
+impl LogConfig {
+    /// Load [LogConfig] from a TOML, YAML or JSON file.
+    ///
+    /// The format is picked by the file extension, or guessed from the content
+    /// when the extension is unknown. The result can be passed to [crate::new].
+    ///
+    /// ```no_run
+    /// use simple_log::LogConfig;
+    ///
+    /// fn main() -> Result<(), String> {
+    ///     let config = LogConfig::from_path("./log_config.toml")?;
+    ///     simple_log::new(config)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn from_path<P: AsRef<Path>>(path: P) -> SimpleResult<LogConfig> {
+        read_config(path.as_ref())
+    }
+}
+
+/// Init simple-log with the config loaded by [LogConfig::from_path].
+///
+/// ```no_run
+/// #[macro_use]
+/// extern crate simple_log;
+///
+/// fn main() -> Result<(), String> {
+///     simple_log::from_file("./log_config.yaml")?;
+///     info!("init from file");
+///     Ok(())
+/// }
+/// ```
+pub fn from_file<P: AsRef<Path>>(path: P) -> SimpleResult<()> {
+    crate::new(LogConfig::from_path(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(any(feature = "toml", feature = "yaml", feature = "json"))]
+    fn write_config(name: &str, content: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join("simple_log_config_file");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_from_extension() {
+        assert_eq!(
+            ConfigFormat::from_extension("log.TOML"),
+            Some(ConfigFormat::Toml)
+        );
+        assert_eq!(
+            ConfigFormat::from_extension("a/log.yml"),
+            Some(ConfigFormat::Yaml)
+        );
+        assert_eq!(
+            ConfigFormat::from_extension("log.json"),
+            Some(ConfigFormat::Json)
+        );
+        assert_eq!(ConfigFormat::from_extension("log.conf"), None);
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let err = LogConfig::from_path("./not_exist_simple_log.toml").unwrap_err();
+        assert!(matches!(err, SimpleLogError::Io { .. }));
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_from_path_toml() {
+        let path = write_config(
+            "from_path.conf",
+            "level = \"debug,app=warn\"\nout_kind = [\"console\", \"file\"]\nsize = 10\n",
+        );
+        let config = LogConfig::from_path(&path).unwrap();
+        assert_eq!(config.get_level(), "DEBUG");
+        assert_eq!(config.get_size(), 10);
+
+        let path = write_config("bad.toml", "level = \"debug\"\nsize = \"ten\"\n");
+        match LogConfig::from_path(&path).unwrap_err() {
+            SimpleLogError::ParseConfig { line, .. } => assert_eq!(line, Some(2)),
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_from_path_yaml() {
+        let path = write_config("from_path.yaml", "level: info\nout_kind: console\n");
+        let config = LogConfig::from_path(&path).unwrap();
+        assert_eq!(config.get_level(), "INFO");
+
+        let path = write_config("bad.yml", "level: info\nsize: [1\n");
+        match LogConfig::from_path(&path).unwrap_err() {
+            SimpleLogError::ParseConfig { line, .. } => assert!(line.is_some()),
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_from_path_json() {
+        let path = write_config("from_path.cfg", r#"{"level":"warn","out_kind":"file"}"#);
+        let config = LogConfig::from_path(&path).unwrap();
+        assert_eq!(config.get_level(), "WARN");
+
+        let path = write_config("bad.json", "{\n\"level\":\"warn\",\n\"size\":\"a\"}");
+        match LogConfig::from_path(&path).unwrap_err() {
+            SimpleLogError::ParseConfig { line, .. } => assert_eq!(line, Some(3)),
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+}
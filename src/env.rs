@@ -0,0 +1,288 @@
+//! Override [LogConfig] fields with environment variables.
+//!
+//! With the default prefix `SIMPLE_LOG` the variables are:
+//!
+//! ```bash
+//! SIMPLE_LOG_LEVEL="debug,app=warn" # same syntax as `level`
+//! SIMPLE_LOG_PATH=./log/app.log
+//! SIMPLE_LOG_DIRECTORY=/var/log/app
+//! SIMPLE_LOG_SIZE=100 # MB
+//! SIMPLE_LOG_OUT_KIND=console,file
+//! SIMPLE_LOG_ROLL_COUNT=10
+//! SIMPLE_LOG_TIME_FORMAT="%H:%M:%S.%f"
+//! SIMPLE_LOG_ROTATION=size,daily # same syntax as `rotation`
+//! SIMPLE_LOG_MAX_AGE=14d
+//! SIMPLE_LOG_MAX_TOTAL_SIZE=2048 # MB
+//! SIMPLE_LOG_ARCHIVE_PATTERN="app-%Y%m%d-%i.log.gz"
+//! SIMPLE_LOG_COMPRESSION=zstd:19 # same syntax as `compression`
+//! SIMPLE_LOG_ROLL_ON_STARTUP=true
+//! SIMPLE_LOG_CURRENT_LINK=current.log
+//! SIMPLE_LOG_EXTERNAL_ROTATION=true
+//! SIMPLE_LOG_PATTERN="{d} [{l}] {m}{n}"
+//! SIMPLE_LOG_FORMAT=json # same syntax as `format`
+//! SIMPLE_LOG_SERVICE_NAME=api
+//! SIMPLE_LOG_SERVICE_VERSION=1.4.2
+//! SIMPLE_LOG_SERVICE_ENVIRONMENT=production
+//! SIMPLE_LOG_OTLP_ENDPOINT=http://collector:4318/v1/logs
+//! ```
+//!
+//! The `SERVICE_*` variables replace the whole `service` of lower layers, and
+//! `OTLP_ENDPOINT` the whole `otlp`, with defaults for the rest.
+//!
+//! Fields that are lists or tables have no variable: `files`, `routes`, `output_levels`,
+//! `output_patterns`, `output_formats`, `json_fields`, and the `otlp` headers, resource
+//! and batching. Set them in a config file or in code.
+
+use crate::format::Service;
+use crate::level::parse_level;
+use crate::otlp::OtlpOutput;
+use crate::out_kind::OutKind;
+use crate::partial::{ConfigSource, PartialLogConfig};
+use crate::{LogConfig, SimpleLogError, SimpleResult};
+use std::env::VarError;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// The prefix used by [from_env].
+pub const DEFAULT_ENV_PREFIX: &str = "SIMPLE_LOG";
+
+/// Read `{prefix}_{name}`, empty values are treated as unset.
+fn env_var(prefix: &str, name: &str) -> SimpleResult<Option<(String, String)>> {
+    let var = format!("{}_{}", prefix, name);
+    match std::env::var(&var) {
+        Ok(value) if value.trim().is_empty() => Ok(None),
+        Ok(value) => Ok(Some((var, value))),
+        Err(VarError::NotPresent) => Ok(None),
+        Err(e) => Err(SimpleLogError::Env {
+            var,
+            message: e.to_string(),
+        }),
+    }
+}
+
+fn parse_env<T>(var: String, value: &str) -> SimpleResult<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    value
+        .trim()
+        .parse()
+        .map_err(|e: T::Err| SimpleLogError::Env {
+            var,
+            message: e.to_string(),
+        })
+}
+
+impl PartialLogConfig {
+    /// Read a layer from the `{prefix}_*` environment variables that are set.
+    ///
+    /// See the module docs for the list of variables.
+    pub fn from_env(prefix: &str) -> SimpleResult<PartialLogConfig> {
+        let mut layer = PartialLogConfig::default();
+        if let Some((var, value)) = env_var(prefix, "LEVEL")? {
+            let level = parse_level(value.trim()).map_err(|e| SimpleLogError::Env {
+                var,
+                message: e.to_string(),
+            })?;
+            layer.level = Some(level);
+        }
+        if let Some((_, value)) = env_var(prefix, "PATH")? {
+            layer.path = Some(value);
+        }
+        if let Some((_, value)) = env_var(prefix, "DIRECTORY")? {
+            layer.directory = Some(value);
+        }
+        if let Some((var, value)) = env_var(prefix, "SIZE")? {
+            layer.size = Some(parse_env(var, &value)?);
+        }
+        if let Some((var, value)) = env_var(prefix, "OUT_KIND")? {
+            let out_kind = value
+                .split(',')
+                .filter(|kind| !kind.trim().is_empty())
+                .map(|kind| parse_env::<OutKind>(var.clone(), kind))
+                .collect::<SimpleResult<_>>()?;
+            layer.out_kind = Some(out_kind);
+        }
+        if let Some((var, value)) = env_var(prefix, "ROLL_COUNT")? {
+            layer.roll_count = Some(parse_env(var, &value)?);
+        }
+        if let Some((_, value)) = env_var(prefix, "TIME_FORMAT")? {
+            layer.time_format = Some(value);
+        }
+        if let Some((var, value)) = env_var(prefix, "ROTATION")? {
+            layer.rotation = Some(parse_env(var, &value)?);
+        }
+        if let Some((var, value)) = env_var(prefix, "MAX_AGE")? {
+            let max_age =
+                humantime::parse_duration(value.trim()).map_err(|e| SimpleLogError::Env {
+                    var,
+                    message: e.to_string(),
+                })?;
+            layer.max_age = Some(max_age);
+        }
+        if let Some((var, value)) = env_var(prefix, "MAX_TOTAL_SIZE")? {
+            layer.max_total_size = Some(parse_env(var, &value)?);
+        }
+        if let Some((_, value)) = env_var(prefix, "ARCHIVE_PATTERN")? {
+            layer.archive_pattern = Some(value);
+        }
+        if let Some((var, value)) = env_var(prefix, "COMPRESSION")? {
+            layer.compression = Some(parse_env(var, &value)?);
+        }
+        if let Some((var, value)) = env_var(prefix, "ROLL_ON_STARTUP")? {
+            layer.roll_on_startup = Some(parse_env(var, &value)?);
+        }
+        if let Some((_, value)) = env_var(prefix, "CURRENT_LINK")? {
+            layer.current_link = Some(value);
+        }
+        if let Some((var, value)) = env_var(prefix, "EXTERNAL_ROTATION")? {
+            layer.external_rotation = Some(parse_env(var, &value)?);
+        }
+        if let Some((_, value)) = env_var(prefix, "PATTERN")? {
+            layer.pattern = Some(value);
+        }
+        if let Some((var, value)) = env_var(prefix, "FORMAT")? {
+            layer.format = Some(parse_env(var, &value)?);
+        }
+        let service = Service {
+            name: env_var(prefix, "SERVICE_NAME")?.map(|(_, value)| value),
+            version: env_var(prefix, "SERVICE_VERSION")?.map(|(_, value)| value),
+            environment: env_var(prefix, "SERVICE_ENVIRONMENT")?.map(|(_, value)| value),
+        };
+        if service != Service::default() {
+            layer.service = Some(service);
+        }
+        if let Some((_, value)) = env_var(prefix, "OTLP_ENDPOINT")? {
+            layer.otlp = Some(OtlpOutput::new(value.trim()));
+        }
+        Ok(layer.with_source(ConfigSource::Env(prefix.to_string())))
+    }
+}
+
+impl LogConfig {
+    /// Override fields with the `{prefix}_*` environment variables that are set.
+    ///
+    /// See the module docs for the list of variables.
+    ///
+    /// ```rust
+    /// use simple_log::{ConfigSource, LogConfigBuilder};
+    ///
+    /// fn main() -> Result<(), String> {
+    ///     std::env::set_var("APP_LOG_LEVEL", "warn,app::db=trace");
+    ///     std::env::set_var("APP_LOG_OUT_KIND", "console,file");
+    ///
+    ///     let mut config = LogConfigBuilder::builder().level("debug")?.size(10).build();
+    ///     config.apply_env("APP_LOG")?;
+    ///     assert_eq!(config.get_level(), "WARN");
+    ///     assert_eq!(config.get_size(), 10);
+    ///     assert_eq!(config.get_out_kind(), &vec!["console".into(), "file".into()]);
+    ///     assert_eq!(
+    ///         config.get_source("level"),
+    ///         Some(&ConfigSource::Env("APP_LOG".to_string()))
+    ///     );
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn apply_env(&mut self, prefix: &str) -> SimpleResult<()> {
+        self.apply(PartialLogConfig::from_env(prefix)?);
+        Ok(())
+    }
+}
+
+/// Init simple-log from the `SIMPLE_LOG_*` environment variables.
+///
+/// Fields without a variable keep the same defaults as [crate::new].
+///
+/// ```no_run
+/// #[macro_use]
+/// extern crate simple_log;
+///
+/// fn main() -> Result<(), String> {
+///     // SIMPLE_LOG_LEVEL=info SIMPLE_LOG_OUT_KIND=console ./app
+///     simple_log::from_env()?;
+///     info!("init from env");
+///     Ok(())
+/// }
+/// ```
+pub fn from_env() -> SimpleResult<()> {
+    let layer = PartialLogConfig::from_env(DEFAULT_ENV_PREFIX)?;
+    crate::new(PartialLogConfig::merge([layer]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Rotation;
+    use log::LevelFilter;
+
+    #[test]
+    fn test_apply_env() {
+        std::env::set_var("TEST_APPLY_ENV_LEVEL", "info,app=off");
+        std::env::set_var("TEST_APPLY_ENV_PATH", "./log/env.log");
+        std::env::set_var("TEST_APPLY_ENV_SIZE", " 20 ");
+        std::env::set_var("TEST_APPLY_ENV_OUT_KIND", "File");
+        std::env::set_var("TEST_APPLY_ENV_ROLL_COUNT", "");
+        std::env::set_var("TEST_APPLY_ENV_ROTATION", "hourly");
+        std::env::set_var("TEST_APPLY_ENV_MAX_AGE", "12h");
+        std::env::set_var("TEST_APPLY_ENV_MAX_TOTAL_SIZE", "2048");
+        std::env::set_var("TEST_APPLY_ENV_SERVICE_NAME", "api");
+        std::env::set_var("TEST_APPLY_ENV_SERVICE_ENVIRONMENT", "production");
+        std::env::set_var(
+            "TEST_APPLY_ENV_OTLP_ENDPOINT",
+            "http://collector:4318/v1/logs",
+        );
+
+        let mut config = LogConfig {
+            roll_count: 3,
+            ..LogConfig::default()
+        };
+        config.apply_env("TEST_APPLY_ENV").unwrap();
+        assert_eq!(
+            config.level,
+            (LevelFilter::Info, vec![("app", LevelFilter::Off).into()])
+        );
+        assert_eq!(config.path, Some("./log/env.log".to_string()));
+        assert_eq!(config.size, 20);
+        assert_eq!(config.out_kind, vec![OutKind::File]);
+        assert_eq!(config.roll_count, 3);
+        assert_eq!(
+            config.max_age,
+            Some(std::time::Duration::from_secs(12 * 60 * 60))
+        );
+        assert_eq!(config.max_total_size, Some(2048));
+        assert_eq!(
+            config.rotation,
+            Rotation::Time(crate::RotationPeriod::Hourly)
+        );
+        assert_eq!(config.directory, None);
+        assert_eq!(
+            config.service,
+            Service::new("api").environment("production")
+        );
+        assert_eq!(
+            config.otlp,
+            OtlpOutput::new("http://collector:4318/v1/logs")
+        );
+    }
+
+    #[test]
+    fn test_apply_env_error() {
+        std::env::set_var("TEST_ENV_ERROR_SIZE", "10MB");
+        let err = LogConfig::default()
+            .apply_env("TEST_ENV_ERROR")
+            .unwrap_err();
+        match err {
+            SimpleLogError::Env { var, .. } => assert_eq!(var, "TEST_ENV_ERROR_SIZE"),
+            e => panic!("unexpected error: {}", e),
+        }
+
+        std::env::set_var("TEST_ENV_KIND_ERROR_OUT_KIND", "console,syslog");
+        let err = LogConfig::default()
+            .apply_env("TEST_ENV_KIND_ERROR")
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Invalid environment variable TEST_ENV_KIND_ERROR_OUT_KIND"));
+    }
+}
@@ -0,0 +1,147 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// The error type returned by simple-log.
+///
+/// New variants may be added, a `match` on it needs a wildcard arm.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum SimpleLogError {
+    /// The level spec, such as `"debug,app=warn"`, could not be parsed.
+    ///
+    /// `offset` is the byte position in `input` where the parser stopped.
+    ParseLevel {
+        input: String,
+        offset: usize,
+        message: String,
+    },
+    /// A log line pattern, such as `"{d} [{l}] {m}{n}"`, has a bad token.
+    ///
+    /// `offset` is the byte position of the bad token in `input`.
+    ParsePattern {
+        input: String,
+        offset: usize,
+        message: String,
+    },
+    /// The `out_kind` value is not one of the supported kinds.
+    InvalidOutKind(String),
+    /// The `rotation` value is not a supported rotation.
+    InvalidRotation(String),
+    /// The `archive_pattern` value has an unknown token.
+    InvalidArchivePattern(String),
+    /// The `compression` value is not a supported format.
+    InvalidCompression(String),
+    /// The `format` value is not a supported output format.
+    InvalidFormat(String),
+    /// A log file, log directory or config file could not be accessed.
+    Io { path: PathBuf, source: io::Error },
+    /// A config file could not be parsed.
+    ///
+    /// `line` is 1-based, when the parser reports it.
+    ParseConfig {
+        path: PathBuf,
+        line: Option<usize>,
+        message: String,
+    },
+    /// An environment variable override could not be parsed.
+    Env { var: String, message: String },
+    /// The signal handlers could not be installed.
+    Signal(io::Error),
+    /// A background thread, such as the config watcher, could not be started.
+    Thread { name: String, source: io::Error },
+    /// log4rs rejected the generated config, or it needs a disabled cargo feature.
+    Config(String),
+    /// simple-log was already initialized.
+    AlreadyInitialized,
+    /// simple-log has not been initialized yet.
+    NotInitialized,
+    /// Another logger was already installed with the `log` crate.
+    SetLogger(log::SetLoggerError),
+}
+
+impl fmt::Display for SimpleLogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimpleLogError::ParseLevel { message, .. } => {
+                write!(f, "Failed to parse level:\n{}", message)
+            }
+            SimpleLogError::ParsePattern {
+                input,
+                offset,
+                message,
+            } => {
+                let column = input[..*offset].chars().count();
+                write!(
+                    f,
+                    "Failed to parse pattern at offset {}: {}\n{}\n{:>width$}",
+                    offset,
+                    message,
+                    input,
+                    "^",
+                    width = column + 1
+                )
+            }
+            SimpleLogError::InvalidOutKind(msg) => f.write_str(msg),
+            SimpleLogError::InvalidRotation(msg) => f.write_str(msg),
+            SimpleLogError::InvalidArchivePattern(msg) => f.write_str(msg),
+            SimpleLogError::InvalidCompression(msg) => f.write_str(msg),
+            SimpleLogError::InvalidFormat(msg) => f.write_str(msg),
+            SimpleLogError::Io { path, source } => {
+                write!(f, "Failed to access '{}': {}", path.display(), source)
+            }
+            SimpleLogError::ParseConfig {
+                path,
+                line: Some(line),
+                message,
+            } => write!(
+                f,
+                "Failed to parse '{}' at line {}: {}",
+                path.display(),
+                line,
+                message
+            ),
+            SimpleLogError::ParseConfig {
+                path,
+                line: None,
+                message,
+            } => write!(f, "Failed to parse '{}': {}", path.display(), message),
+            SimpleLogError::Env { var, message } => {
+                write!(f, "Invalid environment variable {}: {}", var, message)
+            }
+            SimpleLogError::Signal(e) => write!(f, "Failed to install signal handlers: {}", e),
+            SimpleLogError::Thread { name, source } => {
+                write!(f, "Failed to start thread '{}': {}", name, source)
+            }
+            SimpleLogError::Config(msg) => write!(f, "Invalid log config: {}", msg),
+            SimpleLogError::AlreadyInitialized => f.write_str("simple-log is already initialized"),
+            SimpleLogError::NotInitialized => f.write_str("simple-log is not initialized"),
+            SimpleLogError::SetLogger(e) => write!(f, "Failed to set logger: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SimpleLogError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SimpleLogError::Io { source, .. }
+            | SimpleLogError::Signal(source)
+            | SimpleLogError::Thread { source, .. } => Some(source),
+            SimpleLogError::SetLogger(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<log::SetLoggerError> for SimpleLogError {
+    fn from(e: log::SetLoggerError) -> Self {
+        SimpleLogError::SetLogger(e)
+    }
+}
+
+/// Keep `fn main() -> Result<(), String>` callers compiling with `?`.
+impl From<SimpleLogError> for String {
+    fn from(e: SimpleLogError) -> Self {
+        e.to_string()
+    }
+}
@@ -0,0 +1,140 @@
+//! Level thresholds of single outputs.
+//!
+//! `output_levels` maps output names to a level in the `level` syntax, so an output
+//! gets a minimum level and optional target levels:
+//!
+//! ```toml
+//! level = "debug"
+//! out_kind = ["console", "file"]
+//!
+//! [output_levels]
+//! console = "warn,app=info"
+//! ```
+//!
+//! An output never gets more than the `level` of [crate::LogConfig] lets through.
+
+use crate::level::{deserialize_level, format_level, target_level};
+use crate::InnerLevel;
+use log::Record;
+use log4rs::filter::{Filter, Response};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+
+/// Rejects the records above the level of their target.
+#[derive(Debug)]
+pub(crate) struct OutputLevelFilter(InnerLevel);
+
+impl OutputLevelFilter {
+    pub(crate) fn new(level: InnerLevel) -> OutputLevelFilter {
+        OutputLevelFilter(level)
+    }
+}
+
+impl Filter for OutputLevelFilter {
+    fn filter(&self, record: &Record) -> Response {
+        let level = target_level(&self.0 .1, record.target()).unwrap_or(self.0 .0);
+        if record.level() <= level {
+            Response::Neutral
+        } else {
+            Response::Reject
+        }
+    }
+}
+
+/// A level in the `level` syntax.
+#[derive(Deserialize)]
+#[serde(transparent)]
+struct LevelSpec(#[serde(deserialize_with = "deserialize_level")] InnerLevel);
+
+struct LevelSpecRef<'a>(&'a InnerLevel);
+
+impl Serialize for LevelSpecRef<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format_level(self.0))
+    }
+}
+
+pub(crate) fn serialize_output_levels<S>(
+    levels: &BTreeMap<String, InnerLevel>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_map(
+        levels
+            .iter()
+            .map(|(name, level)| (name, LevelSpecRef(level))),
+    )
+}
+
+pub(crate) fn deserialize_output_levels<'de, D>(
+    deserializer: D,
+) -> Result<BTreeMap<String, InnerLevel>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let levels = BTreeMap::<String, LevelSpec>::deserialize(deserializer)?;
+    Ok(levels
+        .into_iter()
+        .map(|(name, LevelSpec(level))| (name, level))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::parse_level;
+    use log::Level;
+
+    fn response(filter: &OutputLevelFilter, target: &str, level: Level) -> Response {
+        filter.filter(
+            &Record::builder()
+                .target(target)
+                .level(level)
+                .args(format_args!("hello"))
+                .build(),
+        )
+    }
+
+    #[test]
+    fn test_level_filter() {
+        let filter = OutputLevelFilter::new(parse_level("warn,app=info,app::db=off").unwrap());
+        assert!(matches!(
+            response(&filter, "hyper", Level::Warn),
+            Response::Neutral
+        ));
+        assert!(matches!(
+            response(&filter, "hyper", Level::Info),
+            Response::Reject
+        ));
+        assert!(matches!(
+            response(&filter, "app::http", Level::Info),
+            Response::Neutral
+        ));
+        assert!(matches!(
+            response(&filter, "app::db", Level::Error),
+            Response::Reject
+        ));
+    }
+
+    #[test]
+    fn test_output_levels_serde() {
+        #[derive(Serialize, Deserialize)]
+        struct Config {
+            #[serde(
+                serialize_with = "serialize_output_levels",
+                deserialize_with = "deserialize_output_levels"
+            )]
+            output_levels: BTreeMap<String, InnerLevel>,
+        }
+        let json = r#"{"output_levels":{"console":"warn,app=info","file":"debug"}}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            config.output_levels["console"],
+            parse_level("warn,app=info").unwrap()
+        );
+        assert_eq!(serde_json::to_string(&config).unwrap(), json);
+        assert!(serde_json::from_str::<Config>(r#"{"output_levels":{"file":"loud"}}"#).is_err());
+    }
+}
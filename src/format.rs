@@ -0,0 +1,687 @@
+//! Output formats: pattern text, one JSON object per line, logfmt `key=value` lines, or
+//! Elastic Common Schema JSON.
+//!
+//! ```toml
+//! format = "json"          # all outputs
+//!
+//! [output_formats]
+//! console = "text"         # but keep the console readable
+//!
+//! [json_fields]
+//! timestamp = "@t"         # rename a field
+//! thread = ""              # leave it out
+//!
+//! [service]                # service.* of the ecs format
+//! name = "api"
+//! version = "1.4.2"
+//! environment = "production"
+//! ```
+
+use crate::archive::hostname;
+use crate::SimpleLogError;
+use chrono::{Local, SecondsFormat, Utc};
+use log::kv::{self, Key, Value, VisitSource, VisitValue};
+use log::Record;
+use log4rs::encode::{Encode, Write};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Number;
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+const FORMAT_EXPECT: &str = "expect format 'text', 'json', 'logfmt' or 'ecs'";
+
+/// The ECS version the [Format::Ecs] fields follow.
+const ECS_VERSION: &str = "1.6.0";
+
+/// The fields written by [Format::Ecs], key-values with these keys are left out.
+const ECS_FIELDS: [&str; 13] = [
+    "@timestamp",
+    "log.level",
+    "message",
+    "ecs.version",
+    "log.logger",
+    "log.origin.file.name",
+    "log.origin.file.line",
+    "process.pid",
+    "process.thread.name",
+    "host.hostname",
+    "service.name",
+    "service.version",
+    "service.environment",
+];
+
+/// How records are written to an output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    /// The log line pattern, see [crate::LogConfigBuilder::pattern].
+    #[default]
+    Text,
+    /// One JSON object per line, with the names of [JsonFields].
+    Json,
+    /// `time=.. level=INFO target=app msg="disk full" user=alice` lines.
+    Logfmt,
+    /// One Elastic Common Schema JSON object per line, with the [Service] of the config.
+    Ecs,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Text => f.write_str("text"),
+            Format::Json => f.write_str("json"),
+            Format::Logfmt => f.write_str("logfmt"),
+            Format::Ecs => f.write_str("ecs"),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = SimpleLogError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "logfmt" => Ok(Format::Logfmt),
+            "ecs" => Ok(Format::Ecs),
+            _ => Err(SimpleLogError::InvalidFormat(format!(
+                "Invalid format '{}',{}",
+                s, FORMAT_EXPECT
+            ))),
+        }
+    }
+}
+
+impl Serialize for Format {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Format {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// The field names of [Format::Json], an empty name leaves the field out.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct JsonFields {
+    /// RFC 3339 local time with microseconds.
+    pub timestamp: String,
+    pub level: String,
+    pub target: String,
+    pub module: String,
+    pub file: String,
+    pub line: String,
+    /// The thread name, left out for unnamed threads.
+    pub thread: String,
+    pub message: String,
+    /// An object with the key-values of the record, left out when there are none.
+    pub key_values: String,
+}
+
+impl Default for JsonFields {
+    fn default() -> Self {
+        JsonFields {
+            timestamp: "timestamp".to_string(),
+            level: "level".to_string(),
+            target: "target".to_string(),
+            module: "module".to_string(),
+            file: "file".to_string(),
+            line: "line".to_string(),
+            thread: "thread".to_string(),
+            message: "message".to_string(),
+            key_values: "fields".to_string(),
+        }
+    }
+}
+
+impl JsonFields {
+    pub(crate) fn is_default(&self) -> bool {
+        *self == JsonFields::default()
+    }
+
+    /// Fails if two fields share a name, which would write the key twice.
+    pub(crate) fn check(&self) -> Result<(), SimpleLogError> {
+        let names = [
+            &self.timestamp,
+            &self.level,
+            &self.target,
+            &self.module,
+            &self.file,
+            &self.line,
+            &self.thread,
+            &self.message,
+            &self.key_values,
+        ];
+        for (i, name) in names.iter().enumerate() {
+            if !name.is_empty() && names[..i].contains(name) {
+                return Err(SimpleLogError::Config(format!(
+                    "json_fields name '{}' is used more than once",
+                    name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The `service.*` fields of [Format::Ecs], unset fields are left out.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct Service {
+    /// `service.name`, the executable name if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// `service.environment`, e.g. `production`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub environment: Option<String>,
+}
+
+impl Service {
+    pub fn new<S: Into<String>>(name: S) -> Service {
+        Service {
+            name: Some(name.into()),
+            ..Service::default()
+        }
+    }
+
+    pub fn version<S: Into<String>>(mut self, version: S) -> Service {
+        self.version = Some(version.into());
+        self
+    }
+
+    pub fn environment<S: Into<String>>(mut self, environment: S) -> Service {
+        self.environment = Some(environment.into());
+        self
+    }
+
+    pub(crate) fn is_default(&self) -> bool {
+        *self == Service::default()
+    }
+}
+
+/// The file name of the executable without extension.
+pub(crate) fn executable_name() -> Option<String> {
+    let exe = std::env::current_exe().ok()?;
+    Some(exe.file_stem()?.to_string_lossy().into_owned())
+}
+
+/// The key-values attached to `record`, as JSON values.
+pub(crate) fn key_values(record: &Record) -> Vec<(String, serde_json::Value)> {
+    struct Collect(Vec<(String, serde_json::Value)>);
+
+    impl<'kvs> VisitSource<'kvs> for Collect {
+        fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+            self.0.push((key.to_string(), json_value(&value)));
+            Ok(())
+        }
+    }
+
+    let mut collect = Collect(vec![]);
+    // collecting never fails
+    let _ = record.key_values().visit(&mut collect);
+    collect.0
+}
+
+/// The record time, RFC 3339 local time with microseconds.
+fn timestamp() -> String {
+    Local::now().to_rfc3339_opts(SecondsFormat::Micros, false)
+}
+
+/// Numbers, booleans and null keep their JSON type, anything else is a string.
+fn json_value(value: &Value) -> serde_json::Value {
+    struct Convert(serde_json::Value);
+
+    impl<'v> VisitValue<'v> for Convert {
+        fn visit_any(&mut self, value: Value) -> Result<(), kv::Error> {
+            self.0 = serde_json::Value::String(value.to_string());
+            Ok(())
+        }
+
+        fn visit_null(&mut self) -> Result<(), kv::Error> {
+            self.0 = serde_json::Value::Null;
+            Ok(())
+        }
+
+        fn visit_u64(&mut self, value: u64) -> Result<(), kv::Error> {
+            self.0 = value.into();
+            Ok(())
+        }
+
+        fn visit_i64(&mut self, value: i64) -> Result<(), kv::Error> {
+            self.0 = value.into();
+            Ok(())
+        }
+
+        fn visit_f64(&mut self, value: f64) -> Result<(), kv::Error> {
+            self.0 = Number::from_f64(value)
+                .map(serde_json::Value::Number)
+                .unwrap_or_else(|| value.to_string().into());
+            Ok(())
+        }
+
+        fn visit_bool(&mut self, value: bool) -> Result<(), kv::Error> {
+            self.0 = value.into();
+            Ok(())
+        }
+    }
+
+    let mut convert = Convert(serde_json::Value::Null);
+    match value.visit(&mut convert) {
+        Ok(()) => convert.0,
+        Err(_) => serde_json::Value::String(value.to_string()),
+    }
+}
+
+/// Writes a JSON object field by field, keeping the field order.
+pub(crate) struct JsonObject {
+    buf: Vec<u8>,
+}
+
+impl JsonObject {
+    pub(crate) fn new() -> JsonObject {
+        JsonObject { buf: vec![b'{'] }
+    }
+
+    /// Add `key`, unless it is empty.
+    pub(crate) fn field<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> io::Result<()> {
+        if key.is_empty() {
+            return Ok(());
+        }
+        if self.buf.len() > 1 {
+            self.buf.push(b',');
+        }
+        serde_json::to_writer(&mut self.buf, key)?;
+        self.buf.push(b':');
+        serde_json::to_writer(&mut self.buf, value)?;
+        Ok(())
+    }
+
+    /// The object followed by a newline.
+    pub(crate) fn finish(mut self) -> Vec<u8> {
+        self.buf.extend_from_slice(b"}\n");
+        self.buf
+    }
+}
+
+/// Encodes a record as one line of JSON, see [Format::Json].
+#[derive(Debug)]
+pub(crate) struct JsonEncoder {
+    fields: JsonFields,
+}
+
+impl JsonEncoder {
+    pub(crate) fn new(fields: JsonFields) -> JsonEncoder {
+        JsonEncoder { fields }
+    }
+
+    fn line(&self, record: &Record, timestamp: &str) -> io::Result<Vec<u8>> {
+        let fields = &self.fields;
+        let mut object = JsonObject::new();
+        object.field(&fields.timestamp, timestamp)?;
+        object.field(&fields.level, record.level().as_str())?;
+        object.field(&fields.target, record.target())?;
+        object.field(&fields.module, &record.module_path())?;
+        object.field(&fields.file, &record.file())?;
+        object.field(&fields.line, &record.line())?;
+        if let Some(thread) = std::thread::current().name() {
+            object.field(&fields.thread, thread)?;
+        }
+        object.field(&fields.message, &record.args().to_string())?;
+        let key_values = key_values(record);
+        if !key_values.is_empty() {
+            let key_values: serde_json::Map<_, _> = key_values.into_iter().collect();
+            object.field(&fields.key_values, &key_values)?;
+        }
+        Ok(object.finish())
+    }
+}
+
+impl Encode for JsonEncoder {
+    fn encode(&self, w: &mut dyn Write, record: &Record) -> anyhow::Result<()> {
+        w.write_all(&self.line(record, &timestamp())?)?;
+        Ok(())
+    }
+}
+
+/// Encodes a record as one line of ECS JSON, see [Format::Ecs].
+///
+/// Key-values are added as top level fields, unless they clash with [ECS_FIELDS].
+#[derive(Debug)]
+pub(crate) struct EcsEncoder {
+    service: Service,
+    hostname: String,
+    pid: u32,
+}
+
+impl EcsEncoder {
+    pub(crate) fn new(service: &Service) -> EcsEncoder {
+        let mut service = service.clone();
+        if service.name.is_none() {
+            service.name = executable_name();
+        }
+        EcsEncoder {
+            service,
+            hostname: hostname(),
+            pid: std::process::id(),
+        }
+    }
+
+    fn line(&self, record: &Record, timestamp: &str, thread: Option<&str>) -> io::Result<Vec<u8>> {
+        let mut object = JsonObject::new();
+        object.field("@timestamp", timestamp)?;
+        object.field("log.level", record.level().as_str())?;
+        object.field("message", &record.args().to_string())?;
+        object.field("ecs.version", ECS_VERSION)?;
+        object.field("log.logger", record.target())?;
+        if let Some(file) = record.file() {
+            object.field("log.origin.file.name", file)?;
+        }
+        if let Some(line) = record.line() {
+            object.field("log.origin.file.line", &line)?;
+        }
+        object.field("process.pid", &self.pid)?;
+        if let Some(thread) = thread {
+            object.field("process.thread.name", thread)?;
+        }
+        object.field("host.hostname", &self.hostname)?;
+        let service = &self.service;
+        if let Some(name) = &service.name {
+            object.field("service.name", name)?;
+        }
+        if let Some(version) = &service.version {
+            object.field("service.version", version)?;
+        }
+        if let Some(environment) = &service.environment {
+            object.field("service.environment", environment)?;
+        }
+        for (key, value) in key_values(record) {
+            if !ECS_FIELDS.contains(&key.as_str()) {
+                object.field(&key, &value)?;
+            }
+        }
+        Ok(object.finish())
+    }
+}
+
+impl Encode for EcsEncoder {
+    fn encode(&self, w: &mut dyn Write, record: &Record) -> anyhow::Result<()> {
+        let timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+        let line = self.line(record, &timestamp, std::thread::current().name())?;
+        w.write_all(&line)?;
+        Ok(())
+    }
+}
+
+/// Encodes a record as one logfmt line, see [Format::Logfmt].
+#[derive(Debug)]
+pub(crate) struct LogfmtEncoder;
+
+impl LogfmtEncoder {
+    fn line(&self, record: &Record, timestamp: &str) -> String {
+        let mut line = String::new();
+        logfmt_pair(&mut line, "time", timestamp);
+        logfmt_pair(&mut line, "level", record.level().as_str());
+        logfmt_pair(&mut line, "target", record.target());
+        logfmt_pair(&mut line, "msg", &record.args().to_string());
+        for (key, value) in key_values(record) {
+            match value {
+                serde_json::Value::String(value) => logfmt_pair(&mut line, &key, &value),
+                value => logfmt_pair(&mut line, &key, &value.to_string()),
+            }
+        }
+        line.push('\n');
+        line
+    }
+}
+
+impl Encode for LogfmtEncoder {
+    fn encode(&self, w: &mut dyn Write, record: &Record) -> anyhow::Result<()> {
+        w.write_all(self.line(record, &timestamp()).as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Append ` key=value`, keys lose the characters logfmt can't hold, values are quoted
+/// when they are empty or hold spaces, `=`, `"` or control characters.
+fn logfmt_pair(line: &mut String, key: &str, value: &str) {
+    if !line.is_empty() {
+        line.push(' ');
+    }
+    for ch in key.chars() {
+        if ch <= ' ' || ch == '=' || ch == '"' || ch.is_control() {
+            line.push('_');
+        } else {
+            line.push(ch);
+        }
+    }
+    line.push('=');
+    let quote = value.is_empty()
+        || value
+            .chars()
+            .any(|ch| ch <= ' ' || ch == '=' || ch == '"' || ch.is_control());
+    if !quote {
+        line.push_str(value);
+        return;
+    }
+    line.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => line.push_str("\\\""),
+            '\\' => line.push_str("\\\\"),
+            '\n' => line.push_str("\\n"),
+            '\r' => line.push_str("\\r"),
+            '\t' => line.push_str("\\t"),
+            ch if ch.is_control() => line.push_str(&format!("\\u{{{:04x}}}", ch as u32)),
+            ch => line.push(ch),
+        }
+    }
+    line.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::Level;
+
+    fn encode(fields: JsonFields, record: &Record) -> String {
+        let line = JsonEncoder::new(fields)
+            .line(record, "2024-03-01T13:05:09.000000+08:00")
+            .unwrap();
+        String::from_utf8(line).unwrap()
+    }
+
+    #[test]
+    fn test_format_serde() {
+        assert_eq!("JSON".parse::<Format>().unwrap(), Format::Json);
+        assert_eq!(Format::Text.to_string(), "text");
+        assert!("xml".parse::<Format>().is_err());
+        let format: Format = serde_json::from_str(r#""Json""#).unwrap();
+        assert_eq!(format, Format::Json);
+        assert_eq!("logfmt".parse::<Format>().unwrap(), Format::Logfmt);
+        assert_eq!("ECS".parse::<Format>().unwrap(), Format::Ecs);
+        assert_eq!(
+            "xml".parse::<Format>().unwrap_err().to_string(),
+            "Invalid format 'xml',expect format 'text', 'json', 'logfmt' or 'ecs'"
+        );
+    }
+
+    #[test]
+    fn test_json_encoder() {
+        let key_values = [
+            ("user", Value::from("alice")),
+            ("id", Value::from(42u64)),
+            ("ok", Value::from(true)),
+            ("ratio", Value::from(0.5)),
+        ];
+        let record = Record::builder()
+            .level(Level::Info)
+            .target("app::db")
+            .module_path(Some("app::db"))
+            .file(Some("src/db.rs"))
+            .line(Some(7))
+            .args(format_args!("say \"hi\"\n"))
+            .key_values(&key_values)
+            .build();
+        let line = encode(JsonFields::default(), &record);
+        assert!(line.ends_with("}\n"));
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(json["timestamp"], "2024-03-01T13:05:09.000000+08:00");
+        assert_eq!(json["level"], "INFO");
+        assert_eq!(json["target"], "app::db");
+        assert_eq!(json["module"], "app::db");
+        assert_eq!(json["file"], "src/db.rs");
+        assert_eq!(json["line"], 7);
+        assert_eq!(json["message"], "say \"hi\"\n");
+        assert_eq!(
+            json["fields"],
+            serde_json::json!({"user": "alice", "id": 42, "ok": true, "ratio": 0.5})
+        );
+    }
+
+    #[test]
+    fn test_json_fields() {
+        let fields: JsonFields =
+            toml::from_str("timestamp = \"@t\"\nmodule = \"\"\nfile = \"\"\nline = \"\"").unwrap();
+        let record = Record::builder()
+            .level(Level::Warn)
+            .target("app")
+            .args(format_args!("disk full"))
+            .build();
+        let line = encode(fields, &record);
+        let thread = std::thread::current().name().map(str::to_string);
+        let expected = match thread {
+            Some(thread) => format!(
+                r#"{{"@t":"2024-03-01T13:05:09.000000+08:00","level":"WARN","target":"app","thread":"{}","message":"disk full"}}"#,
+                thread
+            ),
+            None => r#"{"@t":"2024-03-01T13:05:09.000000+08:00","level":"WARN","target":"app","message":"disk full"}"#.to_string(),
+        };
+        assert_eq!(line, expected + "\n");
+    }
+
+    #[test]
+    fn test_json_fields_check() {
+        let fields: JsonFields = toml::from_str("module = \"\"\nfile = \"\"").unwrap();
+        assert!(fields.check().is_ok());
+        let fields: JsonFields = toml::from_str("message = \"msg\"\nkey_values = \"msg\"").unwrap();
+        assert_eq!(
+            fields.check().unwrap_err().to_string(),
+            "Invalid log config: json_fields name 'msg' is used more than once"
+        );
+    }
+
+    #[test]
+    fn test_logfmt_encoder() {
+        let key_values = [
+            ("user", Value::from("alice smith")),
+            ("id", Value::from(42u64)),
+            ("ok", Value::from(true)),
+            ("path", Value::from("C:\\tmp")),
+            ("empty", Value::from("")),
+            ("bad key", Value::from("a=b")),
+        ];
+        let record = Record::builder()
+            .level(Level::Info)
+            .target("app::db")
+            .args(format_args!("say \"hi\"\n\tbye\u{7}"))
+            .key_values(&key_values)
+            .build();
+        let line = LogfmtEncoder.line(&record, "2024-03-01T13:05:09.000000+08:00");
+        assert_eq!(
+            line,
+            concat!(
+                r#"time=2024-03-01T13:05:09.000000+08:00 level=INFO target=app::db "#,
+                r#"msg="say \"hi\"\n\tbye\u{0007}" user="alice smith" id=42 ok=true "#,
+                r#"path=C:\tmp empty="" bad_key="a=b""#,
+                "\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_logfmt_plain() {
+        let record = Record::builder()
+            .level(Level::Warn)
+            .target("app")
+            .args(format_args!("done"))
+            .build();
+        let line = LogfmtEncoder.line(&record, "2024-03-01T13:05:09.000000+08:00");
+        assert_eq!(
+            line,
+            "time=2024-03-01T13:05:09.000000+08:00 level=WARN target=app msg=done\n"
+        );
+    }
+
+    fn ecs_encoder(service: Service) -> EcsEncoder {
+        EcsEncoder {
+            service,
+            hostname: "web-1".to_string(),
+            pid: 4242,
+        }
+    }
+
+    #[test]
+    fn test_ecs_golden() {
+        let key_values = [
+            ("user", Value::from("alice")),
+            ("attempts", Value::from(2u64)),
+            ("log.level", Value::from("clash")),
+        ];
+        let record = Record::builder()
+            .level(Level::Error)
+            .target("app::db")
+            .module_path(Some("app::db"))
+            .file(Some("src/db.rs"))
+            .line(Some(7))
+            .args(format_args!("connection \"primary\" lost"))
+            .key_values(&key_values)
+            .build();
+        let service = Service::new("api")
+            .version("1.4.2")
+            .environment("production");
+        let line = ecs_encoder(service)
+            .line(&record, "2024-03-01T05:05:09.120Z", Some("worker-1"))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(line).unwrap(),
+            include_str!("../testdata/ecs/full.json")
+        );
+    }
+
+    #[test]
+    fn test_ecs_golden_minimal() {
+        let record = Record::builder()
+            .level(Level::Info)
+            .target("app")
+            .args(format_args!("started"))
+            .build();
+        let line = ecs_encoder(Service::default())
+            .line(&record, "2024-03-01T05:05:09.120Z", None)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(line).unwrap(),
+            include_str!("../testdata/ecs/minimal.json")
+        );
+    }
+
+    #[test]
+    fn test_service_serde() {
+        let service: Service = toml::from_str("name = \"api\"\nenvironment = \"dev\"").unwrap();
+        assert_eq!(service, Service::new("api").environment("dev"));
+        assert_eq!(
+            serde_json::to_string(&service).unwrap(),
+            r#"{"name":"api","environment":"dev"}"#
+        );
+        let encoder = EcsEncoder::new(&Service::default());
+        assert_eq!(encoder.service.name, executable_name());
+        assert!(encoder.service.name.is_some());
+    }
+}
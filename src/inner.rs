@@ -85,27 +85,47 @@
 //! [examples](https://github.com/baoyachi/simple-log/tree/main/examples).
 //!
 
-use crate::level::{parse_level, LevelInto};
+use crate::archive::ArchiveNames;
+use crate::boost::{ActiveBoosts, LevelBoost};
+use crate::compression::Compression;
+use crate::filter::OutputLevelFilter;
+use crate::format::{EcsEncoder, Format, JsonEncoder, JsonFields, LogfmtEncoder, Service};
+use crate::level::{format_level, parse_level, parse_target_name, LevelInto};
+use crate::otlp::OtlpOutput;
+#[cfg(feature = "otlp")]
+use crate::otlp_export::{OtlpAppender, OtlpExporter};
 use crate::out_kind::OutKind;
-use crate::{InnerLevel, SimpleResult};
+use crate::output::FileOutput;
+use crate::partial::{ConfigSource, FieldSources};
+use crate::pattern::check_pattern;
+use crate::reopen::{KeepRoller, ReopenTrigger, REOPEN_CHECK_INTERVAL};
+use crate::retention::Retention;
+use crate::rotation::{
+    last_write, link_current, ArchiveRoller, Archiver, Clock, LocalClock, Rotation, RotationTrigger,
+};
+use crate::route::{Route, OUTPUT_CONSOLE, OUTPUT_FILE, OUTPUT_OTLP};
+use crate::{InnerLevel, SimpleLogError, SimpleResult, TargetLevel};
 use log::LevelFilter;
 use log4rs::append::console::ConsoleAppender;
-use log4rs::append::rolling_file::policy::compound::roll::fixed_window::FixedWindowRoller;
-use log4rs::append::rolling_file::policy::compound::trigger::size::SizeTrigger;
 use log4rs::append::rolling_file::policy::compound::CompoundPolicy;
 use log4rs::append::rolling_file::RollingFileAppender;
-use log4rs::config::runtime::LoggerBuilder;
+use log4rs::append::Append;
+use log4rs::config::runtime::{AppenderBuilder, LoggerBuilder};
 use log4rs::config::{Appender, Config, Logger, Root};
 use log4rs::encode::pattern::PatternEncoder;
+use log4rs::encode::Encode;
 use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::ffi::OsStr;
-use std::path::{Path, PathBuf};
-use std::sync::Mutex;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
 
 const SIMPLE_LOG_FILE: &str = "simple_log_file";
 const SIMPLE_LOG_CONSOLE: &str = "simple_log_console";
+const SIMPLE_LOG_OTLP: &str = "simple_log_otlp";
 const SIMPLE_LOG_BASE_NAME: &str = "simple_log";
 
 pub const DEFAULT_DATE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S.%f";
@@ -115,19 +135,167 @@ pub const DEFAULT_HOUR_TIME_FORMAT: &str = "%H:%M:%S.%f";
 struct LogConf {
     log_config: LogConfig,
     handle: log4rs::Handle,
+    /// Active level boosts, applied over the target levels of `log_config`.
+    boosts: Vec<LevelBoost>,
+    workers: Workers,
+}
+
+/// Background workers kept across config updates, an update only rebuilds the appenders
+/// in front of them. A new set is taken into use once its config is applied.
+#[derive(Debug, Default)]
+struct Workers {
+    /// Reused while `otlp` and `service` are unchanged.
+    #[cfg(feature = "otlp")]
+    otlp: Option<OtlpExporter>,
+    /// By log file, reused while the file is written.
+    archivers: BTreeMap<PathBuf, Archiver>,
+}
+
+impl LogConf {
+    /// The global config, with the active boosts filled in.
+    fn config(&self) -> LogConfig {
+        LogConfig {
+            boosts: ActiveBoosts(self.boosts.clone()),
+            ..self.log_config.clone()
+        }
+    }
 }
 
 static LOG_CONF: OnceCell<Mutex<LogConf>> = OnceCell::new();
 
-fn init_log_conf(mut log_config: LogConfig) -> SimpleResult<()> {
-    let config = build_config(&mut log_config)?;
-    let handle = log4rs::init_config(config).map_err(|e| e.to_string())?;
-    LOG_CONF.get_or_init(|| Mutex::new(LogConf { log_config, handle }));
+/// The result of [try_init], telling which [LogConfig] is in effect.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InitOutcome {
+    /// The given config was installed.
+    Installed(LogConfig),
+    /// simple-log was already initialized, the given config was ignored.
+    /// Holds the config that is in effect.
+    AlreadyInitialized(LogConfig),
+}
+
+impl InitOutcome {
+    /// Returns `true` if the given config was installed.
+    pub fn is_installed(&self) -> bool {
+        matches!(self, InitOutcome::Installed(_))
+    }
+
+    /// The config that is in effect after the call.
+    pub fn config(&self) -> &LogConfig {
+        match self {
+            InitOutcome::Installed(config) | InitOutcome::AlreadyInitialized(config) => config,
+        }
+    }
+
+    pub fn into_config(self) -> LogConfig {
+        match self {
+            InitOutcome::Installed(config) | InitOutcome::AlreadyInitialized(config) => config,
+        }
+    }
+}
+
+fn init_log_conf(mut log_config: LogConfig) -> SimpleResult<InitOutcome> {
+    let mut installed = None;
+    let log_conf = LOG_CONF.get_or_try_init(|| {
+        let (config, workers) = build_config(&mut log_config, &[], &Workers::default(), true)?;
+        let handle = log4rs::init_config(config)?;
+        installed = Some(log_config.clone());
+        Ok::<_, SimpleLogError>(Mutex::new(LogConf {
+            log_config,
+            handle,
+            boosts: vec![],
+            workers,
+        }))
+    })?;
+    match installed {
+        Some(config) => Ok(InitOutcome::Installed(config)),
+        None => {
+            let guard = log_conf.lock().unwrap_or_else(PoisonError::into_inner);
+            Ok(InitOutcome::AlreadyInitialized(guard.config()))
+        }
+    }
+}
+
+/// Like [init_log_conf], but a second initialization is an error.
+fn init_log_conf_once(log_config: LogConfig) -> SimpleResult<()> {
+    match init_log_conf(log_config)? {
+        InitOutcome::Installed(_) => Ok(()),
+        InitOutcome::AlreadyInitialized(_) => Err(SimpleLogError::AlreadyInitialized),
+    }
+}
+
+/// Lock the global config, or report [SimpleLogError::NotInitialized] when [new] has not run yet.
+fn lock_log_conf() -> SimpleResult<MutexGuard<'static, LogConf>> {
+    let log_conf = LOG_CONF.get().ok_or(SimpleLogError::NotInitialized)?;
+    Ok(log_conf.lock().unwrap_or_else(PoisonError::into_inner))
+}
+
+/// Reopen the log files, e.g. after logrotate moved them away.
+///
+/// The log4rs config is rebuilt from the current [LogConfig], the files are not rolled.
+///
+/// ```rust
+/// use simple_log::LogConfigBuilder;
+///
+/// fn main() -> Result<(), String> {
+///     let dir = std::env::temp_dir().join("simple_log_reopen_files_doc");
+///     let _ = std::fs::remove_dir_all(&dir);
+///     let config = LogConfigBuilder::builder()
+///         .directory(dir.display().to_string())
+///         .path("app.log")
+///         .output_file()
+///         .external_rotation(true)
+///         .build();
+///     simple_log::new(config)?;
+///
+///     std::fs::rename(dir.join("app.log"), dir.join("app.log.1")).unwrap();
+///     simple_log::reopen_files()?;
+///     assert!(dir.join("app.log").exists());
+///     Ok(())
+/// }
+/// ```
+pub fn reopen_files() -> SimpleResult<()> {
+    let mut guard = lock_log_conf()?;
+    let LogConf {
+        log_config,
+        handle,
+        boosts,
+        workers,
+    } = &mut *guard;
+    let (config, next) = build_config(log_config, boosts, workers, false)?;
+    handle.set_config(config);
+    *workers = next;
     Ok(())
 }
 
+/// Returns `true` once simple-log has been initialized.
+///
+/// The runtime methods [get_log_conf], [update_log_conf] and [update_log_level]
+/// return [SimpleLogError::NotInitialized] until then.
+///
+/// ```rust
+/// use simple_log::SimpleLogError;
+///
+/// assert!(!simple_log::is_initialized());
+/// assert!(matches!(
+///     simple_log::get_log_conf(),
+///     Err(SimpleLogError::NotInitialized)
+/// ));
+/// assert!(matches!(
+///     simple_log::update_log_level("info"),
+///     Err(SimpleLogError::NotInitialized)
+/// ));
+///
+/// simple_log::console("debug").unwrap();
+/// assert!(simple_log::is_initialized());
+/// ```
+pub fn is_initialized() -> bool {
+    LOG_CONF.get().is_some()
+}
+
 /// Update simple-log global config [LogConfig].
 ///
+/// Returns [SimpleLogError::NotInitialized] if simple-log has not been initialized yet.
+///
 /// ```rust
 /// #[macro_use]
 /// extern crate simple_log;
@@ -169,16 +337,19 @@ fn init_log_conf(mut log_config: LogConfig) -> SimpleResult<()> {
 /// }
 ///```
 pub fn update_log_conf(mut log_config: LogConfig) -> SimpleResult<LogConfig> {
-    let log_conf = LOG_CONF.get().unwrap();
-    let mut guard = log_conf.lock().unwrap();
-    let config = build_config(&mut log_config)?;
+    let mut guard = lock_log_conf()?;
+    let guard = &mut *guard;
+    let (config, workers) = build_config(&mut log_config, &guard.boosts, &guard.workers, false)?;
     guard.log_config = log_config;
     guard.handle.set_config(config);
-    Ok(guard.log_config.clone())
+    guard.workers = workers;
+    Ok(guard.config())
 }
 
 /// update simple-log global config log level.
 ///
+/// Returns [SimpleLogError::NotInitialized] if simple-log has not been initialized yet.
+///
 /// # Examples
 ///
 /// ```rust
@@ -202,16 +373,126 @@ pub fn update_log_conf(mut log_config: LogConfig) -> SimpleResult<LogConfig> {
 /// ```
 ///
 pub fn update_log_level<S: LevelInto>(level: S) -> SimpleResult<LogConfig> {
-    let log_conf = LOG_CONF.get().unwrap();
-    let mut guard = log_conf.lock().unwrap();
-    guard.log_config.set_level(level)?;
-    let config = build_config(&mut guard.log_config)?;
+    modify_log_conf(|log_config| log_config.set_level(level))
+}
+
+/// Edit a copy of the global [LogConfig] and apply it, the global config is kept on error.
+pub(crate) fn modify_log_conf<F>(f: F) -> SimpleResult<LogConfig>
+where
+    F: FnOnce(&mut LogConfig) -> SimpleResult<()>,
+{
+    let mut guard = lock_log_conf()?;
+    let guard = &mut *guard;
+    let mut log_config = guard.log_config.clone();
+    f(&mut log_config)?;
+    // a value changed at runtime comes from code now
+    if log_config.level != guard.log_config.level {
+        log_config.sources.insert("level", ConfigSource::Code);
+    }
+    if log_config.routes != guard.log_config.routes {
+        log_config.sources.insert("routes", ConfigSource::Code);
+    }
+    let (config, workers) = build_config(&mut log_config, &guard.boosts, &guard.workers, false)?;
+    guard.log_config = log_config;
+    guard.handle.set_config(config);
+    guard.workers = workers;
+    Ok(guard.config())
+}
+
+/// Edit the active level boosts and apply them.
+pub(crate) fn modify_boosts<F>(f: F) -> SimpleResult<()>
+where
+    F: FnOnce(&mut Vec<LevelBoost>),
+{
+    let mut guard = lock_log_conf()?;
+    let guard = &mut *guard;
+    let mut boosts = guard.boosts.clone();
+    f(&mut boosts);
+    let mut log_config = guard.log_config.clone();
+    let (config, workers) = build_config(&mut log_config, &boosts, &guard.workers, false)?;
+    guard.boosts = boosts;
     guard.handle.set_config(config);
-    Ok(guard.log_config.clone())
+    guard.workers = workers;
+    Ok(())
+}
+
+/// The active level boosts.
+pub(crate) fn get_boosts() -> SimpleResult<Vec<LevelBoost>> {
+    Ok(lock_log_conf()?.boosts.clone())
+}
+
+/// Set the level of one target, keeping the other target levels.
+///
+/// # Examples
+///
+/// ```rust
+/// use log::LevelFilter;
+///
+/// fn main() -> Result<(), String> {
+///     simple_log::console("info,app=warn,hyper=off")?;
+///
+///     simple_log::set_target_level("app::db", LevelFilter::Trace)?;
+///     simple_log::set_target_level("app", LevelFilter::Debug)?;
+///     let config = simple_log::remove_target_level("hyper")?;
+///     assert_eq!(config.get_level(), "INFO");
+///     assert_eq!(config.get_source("level"), Some(&simple_log::ConfigSource::Code));
+///
+///     let targets: Vec<_> = simple_log::target_levels()?
+///         .iter()
+///         .map(|t| (t.get_name().to_string(), t.get_level()))
+///         .collect();
+///     assert_eq!(
+///         targets,
+///         vec![
+///             ("app".to_string(), LevelFilter::Debug),
+///             ("app::db".to_string(), LevelFilter::Trace),
+///         ]
+///     );
+///
+///     simple_log::reset_target_levels()?;
+///     assert!(simple_log::target_levels()?.is_empty());
+///     Ok(())
+/// }
+/// ```
+pub fn set_target_level<S: AsRef<str>>(target: S, level: LevelFilter) -> SimpleResult<LogConfig> {
+    modify_log_conf(|log_config| log_config.set_target_level(target.as_ref(), level))
+}
+
+/// Remove the level of one target, it falls back to the root level.
+pub fn remove_target_level<S: AsRef<str>>(target: S) -> SimpleResult<LogConfig> {
+    modify_log_conf(|log_config| {
+        log_config.remove_target_level(target.as_ref());
+        Ok(())
+    })
+}
+
+/// Remove all target levels, keeping the root level.
+pub fn reset_target_levels() -> SimpleResult<LogConfig> {
+    modify_log_conf(|log_config| {
+        log_config.level.1.clear();
+        Ok(())
+    })
+}
+
+/// Replace the routes of the global config, see [Route].
+pub fn update_routes(routes: Vec<Route>) -> SimpleResult<LogConfig> {
+    modify_log_conf(|log_config| {
+        log_config.routes = routes;
+        Ok(())
+    })
+}
+
+/// The target levels of the global config.
+pub fn target_levels() -> SimpleResult<Vec<TargetLevel>> {
+    Ok(lock_log_conf()?.log_config.level.1.clone())
 }
 
 /// Get simple-log global config [LogConfig]
 ///
+/// Use [LogConfig::get_source] on the result to see which layer each value came from.
+///
+/// Returns [SimpleLogError::NotInitialized] if simple-log has not been initialized yet.
+///
 /// ```rust
 /// #[macro_use]
 /// extern crate simple_log;
@@ -238,13 +519,14 @@ pub fn update_log_level<S: LevelInto>(level: S) -> SimpleResult<LogConfig> {
 /// }
 /// ```
 pub fn get_log_conf() -> SimpleResult<LogConfig> {
-    let log_conf = LOG_CONF.get().unwrap();
-    let config = log_conf.lock().unwrap().log_config.clone();
+    let config = lock_log_conf()?.config();
     Ok(config)
 }
 
-use crate::level::deserialize_level;
-use crate::out_kind::deserialize_out_kind;
+use crate::filter::{deserialize_output_levels, serialize_output_levels};
+use crate::level::{deserialize_level, serialize_level};
+use crate::out_kind::{deserialize_out_kind, serialize_out_kind};
+use crate::retention::{deserialize_max_age, serialize_max_age};
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -253,16 +535,96 @@ pub struct LogConfig {
     pub path: Option<String>,
     #[serde(default)]
     pub directory: Option<String>,
-    #[serde(deserialize_with = "deserialize_level")]
+    #[serde(
+        serialize_with = "serialize_level",
+        deserialize_with = "deserialize_level"
+    )]
     pub level: InnerLevel,
     #[serde(default)]
     pub size: u64,
-    #[serde(deserialize_with = "deserialize_out_kind", default)]
+    #[serde(
+        serialize_with = "serialize_out_kind",
+        deserialize_with = "deserialize_out_kind",
+        default
+    )]
     pub out_kind: Vec<OutKind>,
     #[serde(default)]
     pub roll_count: u32,
     #[serde(default)]
     pub time_format: Option<String>,
+    /// When the log file is rolled, by `size` unless set, see [Rotation].
+    #[serde(default)]
+    pub rotation: Rotation,
+    /// Delete archives older than this, e.g. `"14d"`. Keep them by `roll_count` only if unset.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_max_age",
+        deserialize_with = "deserialize_max_age"
+    )]
+    pub max_age: Option<Duration>,
+    /// Delete the oldest archives once all archives together exceed this size(MB).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_total_size: Option<u64>,
+    /// Archive file name pattern, e.g. `app-%Y%m%d-%i.log.gz`,
+    /// see [LogConfigBuilder::archive_pattern].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive_pattern: Option<String>,
+    /// How archives are compressed, gzip by default, see [Compression].
+    #[serde(default)]
+    pub compression: Compression,
+    /// Archive the log file left by the last run when simple-log is initialized.
+    #[serde(default)]
+    pub roll_on_startup: bool,
+    /// Keep a symlink to the active log file, relative to its directory, e.g. `current.log`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_link: Option<String>,
+    /// Leave rotation to an external tool such as logrotate, and reopen the moved file.
+    #[serde(default)]
+    pub external_rotation: bool,
+    /// Named file outputs, written next to the `path` output, see [FileOutput].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<FileOutput>,
+    /// Send the records of a target to chosen outputs, see [Route].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub routes: Vec<Route>,
+    /// The level of single outputs by output name, in the `level` syntax, e.g. `warn,app=info`.
+    #[serde(
+        default,
+        skip_serializing_if = "BTreeMap::is_empty",
+        serialize_with = "serialize_output_levels",
+        deserialize_with = "deserialize_output_levels"
+    )]
+    pub output_levels: BTreeMap<String, InnerLevel>,
+    /// The log line pattern of all outputs, replacing the default pattern and `time_format`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// The pattern of single outputs by output name, over `pattern`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub output_patterns: BTreeMap<String, String>,
+    /// Text or JSON for all outputs, see [Format].
+    #[serde(default)]
+    pub format: Format,
+    /// The format of single outputs by output name, over `format`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub output_formats: BTreeMap<String, Format>,
+    /// The field names of [Format::Json].
+    #[serde(default, skip_serializing_if = "JsonFields::is_default")]
+    pub json_fields: JsonFields,
+    /// The `service.*` fields of [Format::Ecs].
+    #[serde(default, skip_serializing_if = "Service::is_default")]
+    pub service: Service,
+    /// The collector of the `otlp` out kind.
+    #[serde(default, skip_serializing_if = "OtlpOutput::is_default")]
+    pub otlp: OtlpOutput,
+    /// Where each field value came from, see [LogConfig::get_source].
+    #[doc(hidden)]
+    #[serde(skip)]
+    pub sources: FieldSources,
+    /// The active level boosts, filled in by [get_log_conf], see [LogConfig::get_boosts].
+    #[doc(hidden)]
+    #[serde(skip)]
+    pub boosts: ActiveBoosts,
 }
 
 impl Default for LogConfig {
@@ -275,6 +637,26 @@ impl Default for LogConfig {
             out_kind: vec![],
             roll_count: 0,
             time_format: None,
+            rotation: Rotation::Size,
+            max_age: None,
+            max_total_size: None,
+            archive_pattern: None,
+            compression: Compression::default(),
+            roll_on_startup: false,
+            current_link: None,
+            external_rotation: false,
+            files: vec![],
+            routes: vec![],
+            output_levels: BTreeMap::new(),
+            pattern: None,
+            output_patterns: BTreeMap::new(),
+            format: Format::Text,
+            output_formats: BTreeMap::new(),
+            json_fields: JsonFields::default(),
+            service: Service::default(),
+            otlp: OtlpOutput::default(),
+            sources: FieldSources::default(),
+            boosts: ActiveBoosts::default(),
         }
     }
 }
@@ -319,12 +701,115 @@ impl LogConfig {
         self.time_format.as_ref()
     }
 
+    pub fn get_rotation(&self) -> Rotation {
+        self.rotation
+    }
+
+    pub fn get_max_age(&self) -> Option<Duration> {
+        self.max_age
+    }
+
+    pub fn get_max_total_size(&self) -> Option<u64> {
+        self.max_total_size
+    }
+
+    pub fn get_archive_pattern(&self) -> Option<&String> {
+        self.archive_pattern.as_ref()
+    }
+
+    pub fn get_compression(&self) -> Compression {
+        self.compression
+    }
+
+    pub fn get_roll_on_startup(&self) -> bool {
+        self.roll_on_startup
+    }
+
+    pub fn get_current_link(&self) -> Option<&String> {
+        self.current_link.as_ref()
+    }
+
+    pub fn get_external_rotation(&self) -> bool {
+        self.external_rotation
+    }
+
+    pub fn get_files(&self) -> &[FileOutput] {
+        &self.files
+    }
+
+    pub fn get_routes(&self) -> &[Route] {
+        &self.routes
+    }
+
+    /// The level of the output named `output`, in the `level` syntax, e.g. `warn,app=info`.
+    pub fn get_output_level(&self, output: &str) -> Option<String> {
+        self.output_levels.get(output).map(format_level)
+    }
+
+    pub fn get_pattern(&self) -> Option<&String> {
+        self.pattern.as_ref()
+    }
+
+    pub fn get_output_pattern(&self, output: &str) -> Option<&String> {
+        self.output_patterns.get(output)
+    }
+
+    pub fn get_format(&self) -> Format {
+        self.format
+    }
+
+    pub fn get_output_format(&self, output: &str) -> Option<Format> {
+        self.output_formats.get(output).copied()
+    }
+
+    pub fn get_json_fields(&self) -> &JsonFields {
+        &self.json_fields
+    }
+
+    pub fn get_service(&self) -> &Service {
+        &self.service
+    }
+
+    pub fn get_otlp(&self) -> &OtlpOutput {
+        &self.otlp
+    }
+
+    /// The config of the named file `output`, unset values are taken from `self`.
+    fn file_output(&self, output: &FileOutput) -> LogConfig {
+        LogConfig {
+            path: Some(output.path.clone()),
+            size: output.size.unwrap_or(self.size),
+            roll_count: output.roll_count.unwrap_or(self.roll_count),
+            archive_pattern: output.archive_pattern.clone(),
+            current_link: None,
+            files: vec![],
+            ..self.clone()
+        }
+    }
+
     pub(crate) fn set_level<T: LevelInto>(&mut self, level: T) -> SimpleResult<()> {
         let level = level.into_level();
         let level = parse_level(level)?;
         self.level = level;
         Ok(())
     }
+
+    pub(crate) fn set_target_level(
+        &mut self,
+        target: &str,
+        level: LevelFilter,
+    ) -> SimpleResult<()> {
+        let target = parse_target_name(target)?;
+        match self.level.1.iter_mut().find(|t| t.name == target) {
+            Some(target_level) => target_level.level = level,
+            None => self.level.1.push((target, level).into()),
+        }
+        Ok(())
+    }
+
+    pub(crate) fn remove_target_level(&mut self, target: &str) {
+        self.level.1.retain(|t| t.name != target);
+    }
 }
 
 /// The [LogConfig] with builder wrapper.
@@ -402,11 +887,254 @@ impl LogConfigBuilder {
         self
     }
 
+    /// Send records to an OpenTelemetry collector as OTLP/HTTP JSON, needs the `otlp` feature.
+    ///
+    /// Records are sent in batches from a background thread, call `log::logger().flush()`
+    /// before exit to send the last batch.
+    ///
+    /// ```rust
+    /// use simple_log::{LogConfigBuilder, OtlpOutput, Service};
+    ///
+    /// let config = LogConfigBuilder::builder()
+    ///     .output_console()
+    ///     .output_otlp(OtlpOutput::new("http://collector:4318/v1/logs").batch_size(100))
+    ///     .service(Service::new("api"))
+    ///     .build();
+    /// assert_eq!(config.get_otlp().batch_size, 100);
+    /// ```
+    pub fn output_otlp(mut self, otlp: OtlpOutput) -> LogConfigBuilder {
+        self.0.out_kind.push(OutKind::Otlp);
+        self.0.otlp = otlp;
+        self
+    }
+
+    /// Add a named file output, next to the `path` output.
+    ///
+    /// ```rust
+    /// use simple_log::{FileOutput, Level, LogConfigBuilder};
+    ///
+    /// fn main() -> Result<(), String> {
+    ///     let dir = std::env::temp_dir().join("simple_log_named_files_doc");
+    ///     let _ = std::fs::remove_dir_all(&dir);
+    ///     let config = LogConfigBuilder::builder()
+    ///         .directory(dir.display().to_string())
+    ///         .path("app.log")
+    ///         .output_file()
+    ///         .output_named_file(FileOutput::new("error", "error.log"))
+    ///         .output_level("error", Level::Warn)?
+    ///         .output_named_file(FileOutput::new("audit", "audit.log").pattern("{m}{n}"))
+    ///         .build();
+    ///     simple_log::new(config)?;
+    ///
+    ///     simple_log::info!("started");
+    ///     simple_log::warn!("disk almost full");
+    ///     let read = |name| std::fs::read_to_string(dir.join(name)).unwrap();
+    ///     assert_eq!(read("app.log").lines().count(), 2);
+    ///     assert!(read("error.log").contains("disk almost full"));
+    ///     assert!(!read("error.log").contains("started"));
+    ///     assert_eq!(read("audit.log"), "started\ndisk almost full\n");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn output_named_file(mut self, output: FileOutput) -> LogConfigBuilder {
+        self.0.files.push(output);
+        self
+    }
+
+    /// Send the records of a target to chosen outputs, see [Route].
+    ///
+    /// ```rust
+    /// use simple_log::{FileOutput, LogConfigBuilder, Route};
+    ///
+    /// fn main() -> Result<(), String> {
+    ///     let dir = std::env::temp_dir().join("simple_log_route_doc");
+    ///     let _ = std::fs::remove_dir_all(&dir);
+    ///     let config = LogConfigBuilder::builder()
+    ///         .directory(dir.display().to_string())
+    ///         .path("app.log")
+    ///         .output_file()
+    ///         .output_named_file(FileOutput::new("audit", "audit.log").routed_only(true))
+    ///         .route(Route::new("app::audit", ["audit"]))
+    ///         .build();
+    ///     simple_log::new(config.clone())?;
+    ///
+    ///     simple_log::info!(target: "app::audit", "user login");
+    ///     simple_log::info!(target: "app", "request");
+    ///     let read = |name| std::fs::read_to_string(dir.join(name)).unwrap();
+    ///     assert!(read("audit.log").contains("user login"));
+    ///     assert!(!read("audit.log").contains("request"));
+    ///     assert!(!read("app.log").contains("user login"));
+    ///
+    ///     // also keep the audit records in app.log
+    ///     simple_log::update_routes(vec![Route::new("app::audit", ["audit"]).additive(true)])?;
+    ///     simple_log::info!(target: "app::audit", "user logout");
+    ///     assert!(read("app.log").contains("user logout"));
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn route(mut self, route: Route) -> LogConfigBuilder {
+        self.0.routes.push(route);
+        self
+    }
+
+    /// Log to the console, limited to `level`, which has the same syntax as [LogConfigBuilder::level].
+    ///
+    /// ```rust
+    /// use simple_log::{Level, LogConfigBuilder};
+    ///
+    /// fn main() -> Result<(), String> {
+    ///     let config = LogConfigBuilder::builder()
+    ///         .level("debug")?
+    ///         .path("./log/output_level.log")
+    ///         .output_file()
+    ///         .output_console_with_level(Level::Warn)?
+    ///         .build();
+    ///     assert_eq!(config.get_output_level("console").unwrap(), "warn");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn output_console_with_level<S: LevelInto>(
+        self,
+        level: S,
+    ) -> SimpleResult<LogConfigBuilder> {
+        self.output_console().output_level(OUTPUT_CONSOLE, level)
+    }
+
+    /// Log to the `path` file, limited to `level`.
+    pub fn output_file_with_level<S: LevelInto>(self, level: S) -> SimpleResult<LogConfigBuilder> {
+        self.output_file().output_level(OUTPUT_FILE, level)
+    }
+
+    /// Limit the output named `output` to `level`, on top of the root [LogConfigBuilder::level].
+    ///
+    /// `output` is `console`, `file` or the name of a [FileOutput].
+    pub fn output_level<N: Into<String>, S: LevelInto>(
+        mut self,
+        output: N,
+        level: S,
+    ) -> SimpleResult<LogConfigBuilder> {
+        let level = parse_level(level.into_level())?;
+        self.0.output_levels.insert(output.into(), level);
+        Ok(self)
+    }
+
     pub fn roll_count(mut self, roll_count: u32) -> LogConfigBuilder {
         self.0.roll_count = roll_count;
         self
     }
 
+    /// Roll the log file by time as well as, or instead of, by size.
+    ///
+    /// ```rust
+    /// use simple_log::{LogConfigBuilder, Rotation, RotationPeriod};
+    ///
+    /// let config = LogConfigBuilder::builder()
+    ///     .rotation(Rotation::SizeOrTime(RotationPeriod::Daily))
+    ///     .build();
+    /// assert_eq!(config.get_rotation().to_string(), "size,daily");
+    /// ```
+    pub fn rotation(mut self, rotation: Rotation) -> LogConfigBuilder {
+        self.0.rotation = rotation;
+        self
+    }
+
+    /// Delete archives older than `max_age`, checked after each roll and at startup.
+    pub fn max_age(mut self, max_age: Duration) -> LogConfigBuilder {
+        self.0.max_age = Some(max_age);
+        self
+    }
+
+    /// Delete the oldest archives once all archives together exceed `max_total_size`(MB),
+    /// checked after each roll and at startup.
+    pub fn max_total_size(mut self, max_total_size: u64) -> LogConfigBuilder {
+        self.0.max_total_size = Some(max_total_size);
+        self
+    }
+
+    /// Name archives by `archive_pattern` instead of `{path}.{index}.gz`.
+    ///
+    /// The pattern is relative to the directory of the log file. Its file name supports the tokens
+    /// `%Y %m %d %H %M %S` for the archived period, or the roll time for size rotation,
+    /// `%i` for an index counting up from 0, `%h` for the hostname and `%p` for the pid.
+    /// Without `%i`, an index is added before `.gz` when the name is taken.
+    ///
+    /// ```rust
+    /// use simple_log::LogConfigBuilder;
+    ///
+    /// let config = LogConfigBuilder::builder()
+    ///     .path("./log/app.log")
+    ///     .rotation("daily".parse().unwrap())
+    ///     .archive_pattern("archive/app-%Y%m%d-%i.log.gz")
+    ///     .build();
+    /// assert_eq!(config.get_archive_pattern().unwrap(), "archive/app-%Y%m%d-%i.log.gz");
+    /// ```
+    pub fn archive_pattern<S: Into<String>>(mut self, archive_pattern: S) -> LogConfigBuilder {
+        self.0.archive_pattern = Some(archive_pattern.into());
+        self
+    }
+
+    /// Compress archives with `compression`, it needs the cargo feature of the format.
+    ///
+    /// Compression runs on a background thread, never on the logging thread.
+    pub fn compression(mut self, compression: Compression) -> LogConfigBuilder {
+        self.0.compression = compression;
+        self
+    }
+
+    /// Archive the log file left by the last run when [crate::new] runs,
+    /// so every run starts with an empty file. Updating the config doesn't roll.
+    ///
+    /// ```rust
+    /// use simple_log::{Compression, LogConfigBuilder};
+    ///
+    /// fn main() -> Result<(), String> {
+    ///     let dir = std::env::temp_dir().join("simple_log_roll_on_startup_doc");
+    ///     let _ = std::fs::remove_dir_all(&dir);
+    ///     std::fs::create_dir_all(&dir).unwrap();
+    ///     std::fs::write(dir.join("app.log"), "last run\n").unwrap();
+    ///
+    ///     let config = LogConfigBuilder::builder()
+    ///         .directory(dir.display().to_string())
+    ///         .path("app.log")
+    ///         .output_file()
+    ///         .compression(Compression::None)
+    ///         .roll_on_startup(true)
+    ///         .current_link("current.log")
+    ///         .build();
+    ///     simple_log::new(config)?;
+    ///
+    ///     std::thread::sleep(std::time::Duration::from_millis(200));
+    ///     assert_eq!(std::fs::read_to_string(dir.join("app.log.0")).unwrap(), "last run\n");
+    ///     assert_eq!(std::fs::read_to_string(dir.join("current.log")).unwrap(), "");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn roll_on_startup(mut self, roll_on_startup: bool) -> LogConfigBuilder {
+        self.0.roll_on_startup = roll_on_startup;
+        self
+    }
+
+    /// Keep the symlink `current_link` pointing at the active log file, for tailing tools.
+    ///
+    /// A relative link is placed in the directory of the log file. It is updated whenever
+    /// the file output is built, and an existing file that is not a symlink is an error.
+    pub fn current_link<S: Into<String>>(mut self, current_link: S) -> LogConfigBuilder {
+        self.0.current_link = Some(current_link.into());
+        self
+    }
+
+    /// Leave rotating the log file to an external tool such as logrotate.
+    ///
+    /// simple-log then never rolls the file, `rotation`, the retention settings and
+    /// `roll_on_startup` are ignored. Before a write, at most once a second, the path is
+    /// compared with the open file by device and inode, and the file is reopened when it
+    /// was moved or replaced. [crate::reopen_files] reopens it on demand, e.g. from a
+    /// logrotate `postrotate` script.
+    pub fn external_rotation(mut self, external_rotation: bool) -> LogConfigBuilder {
+        self.0.external_rotation = external_rotation;
+        self
+    }
+
     /// It's optional method.
     /// Also support default data_time_format:%Y-%m-%d %H:%M:%S.%f
     ///
@@ -416,6 +1144,116 @@ impl LogConfigBuilder {
         self
     }
 
+    /// The log line pattern of all outputs, see the
+    /// [log4rs pattern syntax](https://docs.rs/log4rs/latest/log4rs/encode/pattern/index.html).
+    ///
+    /// It replaces the default pattern, so `time_format` is not used. The pattern is
+    /// checked when simple-log is initialized or updated, an error points to the bad token.
+    ///
+    /// ```rust
+    /// use simple_log::LogConfigBuilder;
+    ///
+    /// fn main() -> Result<(), String> {
+    ///     let dir = std::env::temp_dir().join("simple_log_pattern_doc");
+    ///     let _ = std::fs::remove_dir_all(&dir);
+    ///     let config = LogConfigBuilder::builder()
+    ///         .directory(dir.display().to_string())
+    ///         .path("app.log")
+    ///         .output_file()
+    ///         .output_console()
+    ///         .pattern("{d(%H:%M:%S)} [{l}] {T} {P} {f}:{L} {m}{n}")
+    ///         .output_pattern("console", "[{h({l})}] {m}{n}")
+    ///         .build();
+    ///     simple_log::new(config.clone())?;
+    ///
+    ///     let err = simple_log::update_log_conf(
+    ///         LogConfigBuilder::builder().output_console().pattern("{d} {x}").build(),
+    ///     )
+    ///     .unwrap_err();
+    ///     assert_eq!(
+    ///         err.to_string(),
+    ///         "Failed to parse pattern at offset 5: unknown formatter `x`\n{d} {x}\n     ^"
+    ///     );
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn pattern<S: Into<String>>(mut self, pattern: S) -> LogConfigBuilder {
+        self.0.pattern = Some(pattern.into());
+        self
+    }
+
+    /// The pattern of the output named `output`, over [LogConfigBuilder::pattern].
+    ///
+    /// `output` is `console`, `file` or the name of a [FileOutput].
+    pub fn output_pattern<N: Into<String>, S: Into<String>>(
+        mut self,
+        output: N,
+        pattern: S,
+    ) -> LogConfigBuilder {
+        self.0.output_patterns.insert(output.into(), pattern.into());
+        self
+    }
+
+    /// Write all outputs as `format`, see [Format].
+    ///
+    /// ```rust
+    /// use simple_log::{Format, LogConfigBuilder};
+    ///
+    /// fn main() -> Result<(), String> {
+    ///     let dir = std::env::temp_dir().join("simple_log_format_doc");
+    ///     let _ = std::fs::remove_dir_all(&dir);
+    ///     let config = LogConfigBuilder::builder()
+    ///         .directory(dir.display().to_string())
+    ///         .path("app.log")
+    ///         .output_file()
+    ///         .output_console()
+    ///         .format(Format::Json)
+    ///         .output_format("console", Format::Text)
+    ///         .build();
+    ///     simple_log::new(config)?;
+    ///
+    ///     simple_log::info!(user = "alice"; "login");
+    ///     let line = std::fs::read_to_string(dir.join("app.log")).unwrap();
+    ///     let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+    ///     assert_eq!(json["level"], "INFO");
+    ///     assert_eq!(json["message"], "login");
+    ///     assert_eq!(json["fields"]["user"], "alice");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn format(mut self, format: Format) -> LogConfigBuilder {
+        self.0.format = format;
+        self
+    }
+
+    /// The format of the output named `output`, over [LogConfigBuilder::format].
+    pub fn output_format<N: Into<String>>(mut self, output: N, format: Format) -> LogConfigBuilder {
+        self.0.output_formats.insert(output.into(), format);
+        self
+    }
+
+    /// Rename or leave out the fields of [Format::Json].
+    pub fn json_fields(mut self, json_fields: JsonFields) -> LogConfigBuilder {
+        self.0.json_fields = json_fields;
+        self
+    }
+
+    /// The `service.*` fields of [Format::Ecs].
+    ///
+    /// ```rust
+    /// use simple_log::{Format, LogConfigBuilder, Service};
+    ///
+    /// let config = LogConfigBuilder::builder()
+    ///     .format(Format::Ecs)
+    ///     .service(Service::new("api").version("1.4.2").environment("production"))
+    ///     .build();
+    /// assert_eq!(config.get_service().name.as_deref(), Some("api"));
+    /// ```
+    pub fn service(mut self, service: Service) -> LogConfigBuilder {
+        self.0.service = service;
+        self
+    }
+
     /// Constructs a new `LogConfig` .
     ///
     /// # Examples
@@ -446,6 +1284,9 @@ impl LogConfigBuilder {
 /// This method need pass [LogConfig] param. Your can use [LogConfigBuilder] `build` [LogConfig].
 /// Also you can use [serde] with `Deserialize` init `LogConfig`.
 ///
+/// Calling it twice returns [SimpleLogError::AlreadyInitialized],
+/// use [try_init] or [init_or_update] when init may run more than once.
+///
 /// # Examples
 ///
 /// ```no_run
@@ -473,8 +1314,64 @@ impl LogConfigBuilder {
 pub fn new(log_config: LogConfig) -> SimpleResult<()> {
     let mut log_config = log_config;
     init_default_log(&mut log_config);
-    init_log_conf(log_config)?;
-    Ok(())
+    init_log_conf_once(log_config)
+}
+
+/// Init simple-log with config if it was not initialized yet.
+///
+/// Unlike [new], calling it again is not an error: the first config stays in effect
+/// and the returned [InitOutcome] tells which config won.
+///
+/// # Examples
+///
+/// ```rust
+/// use simple_log::{InitOutcome, LogConfigBuilder};
+///
+/// fn main() -> Result<(), String> {
+///     let first = LogConfigBuilder::builder().level("debug")?.output_console().build();
+///     let outcome = simple_log::try_init(first)?;
+///     assert!(outcome.is_installed());
+///     let installed = outcome.into_config();
+///
+///     let second = LogConfigBuilder::builder().level("warn")?.output_console().build();
+///     let outcome = simple_log::try_init(second)?;
+///     assert_eq!(outcome, InitOutcome::AlreadyInitialized(installed));
+///     Ok(())
+/// }
+/// ```
+pub fn try_init(log_config: LogConfig) -> SimpleResult<InitOutcome> {
+    let mut log_config = log_config;
+    init_default_log(&mut log_config);
+    init_log_conf(log_config)
+}
+
+/// Init simple-log with config the first time, later calls go through [update_log_conf].
+///
+/// Returns the config in effect after the call.
+///
+/// # Examples
+///
+/// ```rust
+/// use simple_log::LogConfigBuilder;
+///
+/// fn main() -> Result<(), String> {
+///     let config = LogConfigBuilder::builder().level("debug")?.output_console().build();
+///     simple_log::init_or_update(config)?;
+///
+///     let config = LogConfigBuilder::builder().level("warn")?.output_console().build();
+///     let out = simple_log::init_or_update(config)?;
+///     assert_eq!("WARN", out.get_level());
+///     assert_eq!(out, simple_log::get_log_conf()?);
+///     Ok(())
+/// }
+/// ```
+pub fn init_or_update(log_config: LogConfig) -> SimpleResult<LogConfig> {
+    let mut log_config = log_config;
+    init_default_log(&mut log_config);
+    match init_log_conf(log_config.clone())? {
+        InitOutcome::Installed(config) => Ok(config),
+        InitOutcome::AlreadyInitialized(_) => update_log_conf(log_config),
+    }
 }
 
 /// This method can quick init simple-log with no configuration.
@@ -519,16 +1416,11 @@ pub fn quick_log_level<S: LevelInto, P: Into<String>>(
     let level = parse_level(level)?;
     let mut config = LogConfig {
         path: path.map(|v| v.into()),
-        directory: None,
         level,
-        size: 0,
-        out_kind: vec![],
-        roll_count: 0,
-        time_format: None,
+        ..LogConfig::default()
     };
     init_default_log(&mut config);
-    init_log_conf(config)?;
-    Ok(())
+    init_log_conf_once(config)
 }
 
 /// Provide init simple-log instance with stdout console on terminal.
@@ -551,16 +1443,12 @@ pub fn console<S: LevelInto>(level: S) -> SimpleResult<()> {
     let level = level.into_level();
     let level = parse_level(level)?;
     let config = LogConfig {
-        path: None,
-        directory: None,
         level,
-        size: 0,
         out_kind: vec![OutKind::Console],
-        roll_count: 0,
         time_format: Some(DEFAULT_DATE_TIME_FORMAT.to_string()),
+        ..LogConfig::default()
     };
-    init_log_conf(config)?;
-    Ok(())
+    init_log_conf_once(config)
 }
 
 /// Provide init simple-log instance with write file.
@@ -595,48 +1483,130 @@ pub fn file<P: Into<String>, S: LevelInto>(
     let level = parse_level(level)?;
     let config = LogConfig {
         path: Some(path.into()),
-        directory: None,
         level,
         size,
         out_kind: vec![OutKind::File],
         roll_count,
         time_format: Some(DEFAULT_DATE_TIME_FORMAT.to_string()),
+        ..LogConfig::default()
     };
-    init_log_conf(config)?;
-    Ok(())
+    init_log_conf_once(config)
 }
 
-fn build_config(log: &mut LogConfig) -> SimpleResult<Config> {
+/// `startup` is set when simple-log is initialized, rather than updated.
+fn build_config(
+    log: &mut LogConfig,
+    boosts: &[LevelBoost],
+    workers: &Workers,
+    startup: bool,
+) -> SimpleResult<(Config, Workers)> {
     let mut config_builder = Config::builder();
     let mut root_builder = Root::builder();
+    // the output names routes can refer to
+    let mut outputs = vec![];
+    // the workers of this config, reused from `workers` where possible
+    let mut next = Workers::default();
+    // the files written, no two outputs may share one
+    let mut paths = vec![];
     for kind in &log.out_kind {
         match kind {
             OutKind::File => {
                 // Check if the directory is set and path is not set; if so, set the default path.
                 if log.directory.is_some() && log.path.is_none() {
                     log.path = Some(log.default_basename());
+                    log.sources.insert("path", ConfigSource::Default);
                 }
 
                 // If the path is now set (either it was initially or we just set it),
                 // proceed to build the appender and configure it.
-                if log.path.is_some() {
-                    config_builder = config_builder
-                        .appender(Appender::builder().build(SIMPLE_LOG_FILE, file_appender(log)?));
+                if let Some(path) = log_path(log) {
+                    paths.push(path);
+                    let encoder = encoder(log, OUTPUT_FILE, None, false)?;
+                    config_builder =
+                        config_builder.appender(output_appender(log, OUTPUT_FILE).build(
+                            SIMPLE_LOG_FILE,
+                            file_appender(log, encoder, startup, workers, &mut next)?,
+                        ));
                     root_builder = root_builder.appender(SIMPLE_LOG_FILE);
+                    outputs.push(OUTPUT_FILE.to_string());
                 }
             }
             OutKind::Console => {
                 let console = ConsoleAppender::builder()
-                    .encoder(Box::new(encoder(log.time_format.as_ref(), true)))
+                    .encoder(encoder(log, OUTPUT_CONSOLE, None, true)?)
                     .build();
-                config_builder = config_builder
-                    .appender(Appender::builder().build(SIMPLE_LOG_CONSOLE, Box::new(console)));
+                config_builder = config_builder.appender(
+                    output_appender(log, OUTPUT_CONSOLE)
+                        .build(SIMPLE_LOG_CONSOLE, Box::new(console)),
+                );
                 root_builder = root_builder.appender(SIMPLE_LOG_CONSOLE);
+                outputs.push(OUTPUT_CONSOLE.to_string());
+            }
+            OutKind::Otlp => {
+                config_builder = config_builder.appender(
+                    output_appender(log, OUTPUT_OTLP)
+                        .build(SIMPLE_LOG_OTLP, otlp_appender(log, workers, &mut next)?),
+                );
+                root_builder = root_builder.appender(SIMPLE_LOG_OTLP);
+                outputs.push(OUTPUT_OTLP.to_string());
             }
         }
     }
 
-    for target in &log.level.1 {
+    for output in &log.files {
+        if [
+            SIMPLE_LOG_FILE,
+            SIMPLE_LOG_CONSOLE,
+            SIMPLE_LOG_OTLP,
+            OUTPUT_FILE,
+            OUTPUT_CONSOLE,
+            OUTPUT_OTLP,
+        ]
+        .contains(&output.name.as_str())
+        {
+            return Err(SimpleLogError::Config(format!(
+                "output name '{}' is reserved",
+                output.name
+            )));
+        }
+        if outputs.contains(&output.name) {
+            return Err(SimpleLogError::Config(format!(
+                "output name '{}' is used more than once",
+                output.name
+            )));
+        }
+        let file_output = log.file_output(output);
+        if let Some(path) = log_path(&file_output) {
+            if paths.contains(&path) {
+                return Err(SimpleLogError::Config(format!(
+                    "output '{}' writes to '{}', which another output writes to",
+                    output.name,
+                    path.display()
+                )));
+            }
+            paths.push(path);
+        }
+        let encoder = encoder(log, &output.name, Some(output), false)?;
+        let appender = output_appender(log, &output.name);
+        let file = file_appender(&file_output, encoder, startup, workers, &mut next)?;
+        config_builder = config_builder.appender(appender.build(&output.name, file));
+        if !output.routed_only {
+            root_builder = root_builder.appender(&output.name);
+        }
+        outputs.push(output.name.clone());
+    }
+
+    check_outputs("level", log.output_levels.keys(), &outputs)?;
+    check_outputs("pattern", log.output_patterns.keys(), &outputs)?;
+    check_outputs("format", log.output_formats.keys(), &outputs)?;
+    log.json_fields.check()?;
+
+    let targets = log.effective_target_levels(boosts);
+    for target in &targets {
+        // a routed target gets its logger below
+        if log.routes.iter().any(|route| route.target == target.name) {
+            continue;
+        }
         config_builder = config_builder.logger(LoggerBuilder::build(
             Logger::builder(),
             &target.name,
@@ -644,36 +1614,142 @@ fn build_config(log: &mut LogConfig) -> SimpleResult<Config> {
         ));
     }
 
+    for route in &log.routes {
+        parse_target_name(&route.target)?;
+        let mut logger = Logger::builder().additive(route.additive);
+        for output in &route.outputs {
+            if !outputs.contains(output) {
+                return Err(SimpleLogError::Config(format!(
+                    "route '{}' refers to unknown output '{}'",
+                    route.target, output
+                )));
+            }
+            logger = logger.appender(match output.as_str() {
+                OUTPUT_FILE => SIMPLE_LOG_FILE,
+                OUTPUT_CONSOLE => SIMPLE_LOG_CONSOLE,
+                OUTPUT_OTLP => SIMPLE_LOG_OTLP,
+                name => name,
+            });
+        }
+        let level = log.level_in(&targets, &route.target);
+        config_builder = config_builder.logger(logger.build(&route.target, level));
+    }
+
     let config = config_builder
         .build(root_builder.build(log.level.0))
-        .map_err(|e| e.to_string())?;
-    Ok(config)
+        .map_err(|e| SimpleLogError::Config(e.to_string()))?;
+    Ok((config, next))
+}
+
+/// An appender in front of the otlp exporter of `workers`, started anew if its settings
+/// changed. The exporter is kept in `next`.
+#[cfg(feature = "otlp")]
+fn otlp_appender(
+    log: &LogConfig,
+    workers: &Workers,
+    next: &mut Workers,
+) -> SimpleResult<Box<dyn Append>> {
+    let exporter = match &workers.otlp {
+        Some(exporter) if exporter.is_for(&log.otlp, &log.service) => exporter.clone(),
+        _ => OtlpExporter::new(&log.otlp, &log.service)?,
+    };
+    next.otlp = Some(exporter.clone());
+    Ok(Box::new(OtlpAppender::new(exporter)))
+}
+
+#[cfg(not(feature = "otlp"))]
+fn otlp_appender(
+    _log: &LogConfig,
+    _workers: &Workers,
+    _next: &mut Workers,
+) -> SimpleResult<Box<dyn Append>> {
+    Err(SimpleLogError::Config(
+        "out_kind 'otlp' needs the `otlp` feature".to_string(),
+    ))
+}
+
+/// An appender builder with the level filter of the output named `output`.
+fn output_appender(log: &LogConfig, output: &str) -> AppenderBuilder {
+    let mut appender = Appender::builder();
+    if let Some(level) = log.output_levels.get(output) {
+        appender = appender.filter(Box::new(OutputLevelFilter::new(level.clone())));
+    }
+    appender
 }
 
 /// check log config,and give default value
-fn init_default_log(log: &mut LogConfig) {
+pub(crate) fn init_default_log(log: &mut LogConfig) {
     if let Some(path) = &log.path {
         if path.trim().is_empty() {
             let file_name = log.default_basename();
             log.path = Some(format!("./tmp/{}", file_name));
+            log.sources.insert("path", ConfigSource::Default);
         }
     }
 
     if log.size == 0 {
-        log.size = 10 //1MB:1*1024*1024
+        log.size = 10; //1MB:1*1024*1024
+        log.sources.insert("size", ConfigSource::Default);
     }
 
     if log.roll_count == 0 {
-        log.roll_count = 10
+        log.roll_count = 10;
+        log.sources.insert("roll_count", ConfigSource::Default);
     }
 
     if log.out_kind.is_empty() {
         log.out_kind
-            .append(&mut vec![OutKind::Console, OutKind::File])
+            .append(&mut vec![OutKind::Console, OutKind::File]);
+        log.sources.insert("out_kind", ConfigSource::Default);
+    }
+}
+
+/// Fails if a setting of `what` is keyed by a name that is not in `outputs`.
+fn check_outputs<'a>(
+    what: &str,
+    mut names: impl Iterator<Item = &'a String>,
+    outputs: &[String],
+) -> SimpleResult<()> {
+    match names.find(|name| !outputs.contains(name)) {
+        Some(name) => Err(SimpleLogError::Config(format!(
+            "output {} for unknown output '{}'",
+            what, name
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// The encoder of the output named `output`, a setting of the named file `file` wins over
+/// the `output_*` entry of `output`, which wins over the setting of all outputs.
+fn encoder(
+    log: &LogConfig,
+    output: &str,
+    file: Option<&FileOutput>,
+    color: bool,
+) -> SimpleResult<Box<dyn Encode>> {
+    let format = file
+        .and_then(|file| file.format)
+        .or(log.output_formats.get(output).copied())
+        .unwrap_or(log.format);
+    match format {
+        Format::Json => return Ok(Box::new(JsonEncoder::new(log.json_fields.clone()))),
+        Format::Logfmt => return Ok(Box::new(LogfmtEncoder)),
+        Format::Ecs => return Ok(Box::new(EcsEncoder::new(&log.service))),
+        Format::Text => {}
     }
+    let pattern = match file
+        .and_then(|file| file.pattern.as_ref())
+        .or(log.output_patterns.get(output))
+        .or(log.pattern.as_ref())
+    {
+        Some(pattern) => pattern.clone(),
+        None => default_pattern(log.time_format.as_ref(), color),
+    };
+    check_pattern(&pattern)?;
+    Ok(Box::new(PatternEncoder::new(&pattern)))
 }
 
-fn encoder(time_format: Option<&String>, color: bool) -> PatternEncoder {
+fn default_pattern(time_format: Option<&String>, color: bool) -> String {
     let time_format = if let Some(format) = time_format {
         format.to_string()
     } else {
@@ -695,39 +1771,347 @@ fn encoder(time_format: Option<&String>, color: bool) -> PatternEncoder {
         pattern += "<{M}:{L}>:{m}{n}";
     }
 
-    PatternEncoder::new(pattern.as_str())
+    pattern
 }
 
-fn file_appender(log: &LogConfig) -> SimpleResult<Box<RollingFileAppender>> {
-    // If the log is written to a file, the path parameter is required
-    let path = log
-        .path
-        .as_ref()
-        .expect("Expected the path to write the log file, but it is empty");
-
-    let mut path = PathBuf::from(path);
+/// The policy which rolls and archives the log file by the configured [Rotation].
+fn archive_policy(
+    log: &LogConfig,
+    path: &Path,
+    startup: bool,
+    workers: &Workers,
+    next: &mut Workers,
+) -> SimpleResult<CompoundPolicy> {
+    log.compression.check_enabled()?;
+    let names = ArchiveNames::new(
+        path,
+        log.archive_pattern.as_deref(),
+        log.rotation.period(),
+        log.compression,
+    )?;
+    let retention = Retention::from_config(log);
+    let io_err = |source| SimpleLogError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    let archiver = match workers.archivers.get(path) {
+        Some(archiver) => archiver.clone(),
+        None => {
+            // a file newly written, apply the retention to what is there
+            names
+                .list(path)
+                .and_then(|archives| retention.cleanup(&archives))
+                .map_err(io_err)?;
+            Archiver::new(path.to_path_buf())
+        }
+    };
+    next.archivers.insert(path.to_path_buf(), archiver.clone());
 
-    if let Some(directory) = &log.directory {
-        let buf = PathBuf::from(directory);
-        path = buf.join(path);
+    let clock: Arc<dyn Clock> = Arc::new(LocalClock);
+    let archive_period = Arc::new(Mutex::new(None));
+    let roll = ArchiveRoller::new(
+        archiver,
+        names,
+        log.compression,
+        retention,
+        clock.clone(),
+        archive_period.clone(),
+    );
+    if startup {
+        roll.archive_leftovers(path, log.rotation.period())
+            .map_err(io_err)?;
     }
+    if startup && log.roll_on_startup {
+        roll.roll_existing(path, log.rotation.period())
+            .map_err(io_err)?;
+    }
+    let trigger = RotationTrigger::new(
+        log.rotation,
+        log.size * 1024 * 1024,
+        clock,
+        last_write(path),
+        archive_period,
+    );
+    Ok(CompoundPolicy::new(Box::new(trigger), Box::new(roll)))
+}
 
-    let roll = FixedWindowRoller::builder()
-        .base(0)
-        .build(
-            format!("{}.{{}}.gz", path.display()).as_str(),
-            log.roll_count,
-        )
-        .map_err(|e| e.to_string())?;
+/// The log file of `log`, joined to `directory` when that is set.
+fn log_path(log: &LogConfig) -> Option<PathBuf> {
+    let path = PathBuf::from(log.path.as_ref()?);
+    let path = match &log.directory {
+        Some(directory) => PathBuf::from(directory).join(path),
+        None => path,
+    };
+    // `./app.log` and `app.log` are the same file
+    Some(
+        path.components()
+            .filter(|c| *c != Component::CurDir)
+            .collect(),
+    )
+}
 
-    let trigger = SizeTrigger::new(log.size * 1024 * 1024);
+/// The appender of the log file of `log`, its archiver is taken from `workers` and kept
+/// in `next`.
+fn file_appender(
+    log: &LogConfig,
+    encoder: Box<dyn Encode>,
+    startup: bool,
+    workers: &Workers,
+    next: &mut Workers,
+) -> SimpleResult<Box<RollingFileAppender>> {
+    // If the log is written to a file, the path parameter is required
+    let path = log_path(log).expect("Expected the path to write the log file, but it is empty");
 
-    let policy = CompoundPolicy::new(Box::new(trigger), Box::new(roll));
+    let policy = if log.external_rotation {
+        CompoundPolicy::new(
+            Box::new(ReopenTrigger::new(path.clone(), REOPEN_CHECK_INTERVAL)),
+            Box::new(KeepRoller),
+        )
+    } else {
+        archive_policy(log, &path, startup, workers, next)?
+    };
 
     let logfile = RollingFileAppender::builder()
-        .encoder(Box::new(encoder(log.time_format.as_ref(), false)))
+        .encoder(encoder)
         .build(path.clone(), Box::new(policy))
-        .map_err(|e| e.to_string())?;
+        .map_err(|source| SimpleLogError::Io {
+            path: path.clone(),
+            source,
+        })?;
+
+    if let Some(link) = &log.current_link {
+        link_current(&path, Path::new(link)).map_err(|source| SimpleLogError::Io {
+            path: PathBuf::from(link),
+            source,
+        })?;
+    }
 
     Ok(Box::new(logfile))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip_config() -> LogConfig {
+        let mut config = LogConfigBuilder::builder()
+            .path("./log/round_trip.log")
+            .directory("/tmp")
+            .size(100)
+            .roll_count(10)
+            .level("debug,app=warn,app::db=trace")
+            .unwrap()
+            .time_format(DEFAULT_HOUR_TIME_FORMAT)
+            .rotation("size,hourly".parse().unwrap())
+            .max_age(Duration::from_secs(14 * 24 * 60 * 60))
+            .max_total_size(2048)
+            .archive_pattern("app-%Y%m%d-%i.log.gz")
+            .compression(Compression::Zstd { level: 19 })
+            .roll_on_startup(true)
+            .current_link("current.log")
+            .external_rotation(true)
+            .output_named_file(FileOutput::new("error", "error.log"))
+            .output_level("error", "warn")
+            .unwrap()
+            .output_named_file(FileOutput::new("audit", "audit.log").routed_only(true))
+            .route(Route::new("app::audit", ["audit"]))
+            .route(Route::new("hyper", ["file", "error"]).additive(true))
+            .output_file()
+            .output_console_with_level("warn,app=info")
+            .unwrap()
+            .pattern("{d} [{l}] {T} {P} {f}:{L} {m}{n}")
+            .output_pattern("console", "[{h({l})}] {m}{n}")
+            .format(Format::Json)
+            .output_format("console", Format::Text)
+            .json_fields(JsonFields {
+                timestamp: "@t".to_string(),
+                thread: String::new(),
+                ..JsonFields::default()
+            })
+            .output_format("error", Format::Ecs)
+            .service(Service::new("api").environment("test"))
+            .build();
+        // the settings only, the out kind needs the otlp feature
+        config.otlp = OtlpOutput::new("http://collector:4318/v1/logs").batch_size(100);
+        config
+    }
+
+    #[test]
+    fn test_set_target_level() {
+        let mut config = LogConfigBuilder::builder()
+            .level("info,app=warn")
+            .unwrap()
+            .build();
+        config.set_target_level("app", LevelFilter::Debug).unwrap();
+        config
+            .set_target_level("app::db", LevelFilter::Trace)
+            .unwrap();
+        assert_eq!(
+            config.level.1,
+            vec![
+                ("app", LevelFilter::Debug).into(),
+                ("app::db", LevelFilter::Trace).into()
+            ]
+        );
+
+        assert!(matches!(
+            config.set_target_level("app db", LevelFilter::Trace),
+            Err(SimpleLogError::ParseLevel { offset: 3, .. })
+        ));
+
+        config.remove_target_level("app");
+        config.remove_target_level("not_exist");
+        assert_eq!(config.level.1, vec![("app::db", LevelFilter::Trace).into()]);
+    }
+
+    #[test]
+    fn test_log_config_json_round_trip() {
+        let config = round_trip_config();
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(json.contains(r#""level":"debug,app=warn,app::db=trace""#));
+        assert!(json.contains(r#""out_kind":["file","console"]"#));
+        assert!(json.contains(r#""rotation":"size,hourly""#));
+        assert!(json.contains(r#""max_age":"14days""#));
+        assert!(json.contains(r#""compression":"zstd:19""#));
+        assert_eq!(serde_json::from_str::<LogConfig>(&json).unwrap(), config);
+    }
+
+    #[test]
+    fn test_log_config_toml_round_trip() {
+        let config = round_trip_config();
+        let toml = toml::to_string(&config).unwrap();
+        assert_eq!(toml::from_str::<LogConfig>(&toml).unwrap(), config);
+
+        let config = LogConfigBuilder::builder().build();
+        let toml = toml::to_string(&config).unwrap();
+        assert_eq!(toml::from_str::<LogConfig>(&toml).unwrap(), config);
+    }
+
+    #[test]
+    fn test_log_config_yaml_round_trip() {
+        let config = round_trip_config();
+        let yaml = serde_yaml::to_string(&config).unwrap();
+        assert_eq!(serde_yaml::from_str::<LogConfig>(&yaml).unwrap(), config);
+    }
+
+    #[test]
+    fn test_route_outputs() {
+        let mut config = LogConfigBuilder::builder()
+            .output_console()
+            .route(Route::new("hyper", ["file"]))
+            .build();
+        let err = build_config(&mut config, &[], &Workers::default(), false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid log config: route 'hyper' refers to unknown output 'file'"
+        );
+
+        config.routes = vec![Route::new("hyper", ["console"])];
+        assert!(build_config(&mut config, &[], &Workers::default(), false).is_ok());
+
+        config.routes = vec![Route::new("hyper db", ["console"])];
+        assert!(build_config(&mut config, &[], &Workers::default(), false).is_err());
+
+        config.routes = vec![];
+        config.output_levels = [("file".to_string(), parse_level("warn").unwrap())].into();
+        let err = build_config(&mut config, &[], &Workers::default(), false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid log config: output level for unknown output 'file'"
+        );
+    }
+
+    #[test]
+    fn test_duplicate_file_outputs() {
+        let mut config = LogConfigBuilder::builder()
+            .path("./log/app.log")
+            .output_file()
+            .output_named_file(FileOutput::new("error", "./log/error.log"))
+            .output_named_file(FileOutput::new("error", "./log/warn.log"))
+            .build();
+        let err = build_config(&mut config, &[], &Workers::default(), false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid log config: output name 'error' is used more than once"
+        );
+
+        config.files = vec![
+            FileOutput::new("error", "./log/error.log"),
+            FileOutput::new("warn", "log/error.log"),
+        ];
+        let err = build_config(&mut config, &[], &Workers::default(), false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid log config: output 'warn' writes to 'log/error.log', which another output writes to"
+        );
+
+        config.files = vec![FileOutput::new("error", "log/app.log")];
+        let err = build_config(&mut config, &[], &Workers::default(), false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid log config: output 'error' writes to 'log/app.log', which another output writes to"
+        );
+
+        config.files = vec![FileOutput::new("error", "./log/error.log")];
+        assert!(build_config(&mut config, &[], &Workers::default(), false).is_ok());
+    }
+
+    #[test]
+    fn test_archiver_reused() {
+        let mut config = LogConfigBuilder::builder()
+            .path("./log/archiver_reused.log")
+            .output_file()
+            .build();
+        let (_, workers) = build_config(&mut config, &[], &Workers::default(), false).unwrap();
+        assert_eq!(workers.archivers.len(), 1);
+
+        config.set_level("debug").unwrap();
+        config.files = vec![FileOutput::new("error", "./log/archiver_reused_error.log")];
+        let (_, next) = build_config(&mut config, &[], &workers, false).unwrap();
+        assert_eq!(next.archivers.len(), 2);
+        for (path, archiver) in &workers.archivers {
+            assert!(next.archivers[path].is_clone_of(archiver));
+        }
+    }
+
+    #[cfg(feature = "otlp")]
+    #[test]
+    fn test_otlp_exporter_reused() {
+        let mut config = LogConfigBuilder::builder()
+            .output_otlp(OtlpOutput::new("http://localhost:4318/v1/logs"))
+            .build();
+        let (_, workers) = build_config(&mut config, &[], &Workers::default(), false).unwrap();
+        let exporter = workers.otlp.clone().unwrap();
+
+        config.set_level("debug").unwrap();
+        let (_, workers) = build_config(&mut config, &[], &workers, false).unwrap();
+        assert!(workers.otlp.as_ref().unwrap().is_clone_of(&exporter));
+
+        config.service = Service::new("api");
+        let (_, workers) = build_config(&mut config, &[], &workers, false).unwrap();
+        assert!(!workers.otlp.as_ref().unwrap().is_clone_of(&exporter));
+
+        config.out_kind = vec![OutKind::Console];
+        let (_, workers) = build_config(&mut config, &[], &workers, false).unwrap();
+        assert!(workers.otlp.is_none());
+    }
+
+    #[test]
+    fn test_otlp_output() {
+        let mut config = LogConfigBuilder::builder()
+            .output_otlp(OtlpOutput::new("https://collector:4318/v1/logs"))
+            .route(Route::new("app::audit", ["otlp"]))
+            .build();
+        let result = build_config(&mut config, &[], &Workers::default(), false)
+            .map(|_| ())
+            .map_err(|e| e.to_string());
+        let expected = if cfg!(feature = "otlp_tls") {
+            Ok(())
+        } else if cfg!(feature = "otlp") {
+            Err("Invalid log config: otlp endpoint 'https://collector:4318/v1/logs' needs the `otlp_tls` feature")
+        } else {
+            Err("Invalid log config: out_kind 'otlp' needs the `otlp` feature")
+        };
+        assert_eq!(result, expected.map_err(str::to_string));
+    }
+}
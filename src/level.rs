@@ -1,12 +1,12 @@
-use crate::InnerLevel;
+use crate::{InnerLevel, SimpleResult, TargetLevel};
 use core::fmt;
 use log::{Level, LevelFilter};
 pub use parser::*;
 use serde::de::DeserializeSeed;
-use serde::{de, Deserializer};
+use serde::{de, Deserializer, Serializer};
 
 pub(crate) mod parser {
-    use crate::{InnerLevel, TargetLevel};
+    use crate::{InnerLevel, SimpleLogError, SimpleResult, TargetLevel};
     use log::LevelFilter;
     use std::str::FromStr;
     use winnow::ascii::{alpha1, multispace0};
@@ -34,14 +34,14 @@ pub(crate) mod parser {
     /// assert_eq!(parse_level(input).unwrap(), (LevelFilter::Error, vec![]));
     ///
     /// let input = "off !!!";
-    /// assert_eq!(parse_level(input).err().unwrap(),
+    /// assert_eq!(parse_level(input).err().unwrap().to_string(),
     /// r###"Failed to parse level:
     /// off !!!
     ///    ^
     /// "###);
     ///
     /// let input = "warning";
-    /// assert_eq!(parse_level(input).err().unwrap(),
+    /// assert_eq!(parse_level(input).err().unwrap().to_string(),
     /// r#"Failed to parse level:
     /// warning
     /// ^
@@ -49,12 +49,18 @@ pub(crate) mod parser {
     ///
     ///
     /// let input = "info,";
-    /// assert_eq!(parse_level(input).err().unwrap(),
+    /// assert_eq!(parse_level(input).err().unwrap().to_string(),
     /// r#"Failed to parse level:
     /// info,
     ///     ^
     /// "#);
     ///
+    /// use simple_log::SimpleLogError;
+    /// match parse_level("info,") {
+    ///     Err(SimpleLogError::ParseLevel { offset, .. }) => assert_eq!(offset, 4),
+    ///     other => panic!("unexpected: {:?}", other),
+    /// }
+    ///
     /// let input = "error,app=off";
     /// assert_eq!(parse_level(input).unwrap(), (LevelFilter::Error, vec![("app", LevelFilter::Off).into()]));
     ///
@@ -72,7 +78,7 @@ pub(crate) mod parser {
     ///
     ///```
     ///
-    pub fn parse_level(input: &str) -> Result<InnerLevel, String> {
+    pub fn parse_level(input: &str) -> SimpleResult<InnerLevel> {
         match (
             level,
             opt((multispace0, ',', repeat(1.., target_level)))
@@ -81,10 +87,25 @@ pub(crate) mod parser {
             .parse(input)
         {
             Ok((level, targets)) => Ok((level, targets)),
-            Err(err) => Err(format!("Failed to parse level:\n{}", err)),
+            Err(err) => Err(SimpleLogError::ParseLevel {
+                input: input.to_string(),
+                offset: err.offset(),
+                message: err.to_string(),
+            }),
         }
     }
 
+    /// Check that `input` is a valid target name, such as `app::db`.
+    pub(crate) fn parse_target_name(input: &str) -> SimpleResult<&str> {
+        target_name
+            .parse(input)
+            .map_err(|err| SimpleLogError::ParseLevel {
+                input: input.to_string(),
+                offset: err.offset(),
+                message: err.to_string(),
+            })
+    }
+
     fn level(input: &mut &str) -> WResult<LevelFilter> {
         alpha1.try_map(LevelFilter::from_str).parse_next(input)
     }
@@ -100,6 +121,41 @@ pub(crate) mod parser {
     }
 }
 
+/// Format [InnerLevel] back into the level spec accepted by [parse_level].
+///
+/// ```rust
+/// use log::LevelFilter;
+/// use simple_log::level::{format_level, parse_level};
+///
+/// let level = (LevelFilter::Debug, vec![("app", LevelFilter::Warn).into()]);
+/// assert_eq!(format_level(&level), "debug,app=warn");
+/// assert_eq!(parse_level(&format_level(&level)).unwrap(), level);
+/// ```
+pub fn format_level(level: &InnerLevel) -> String {
+    let mut out = level.0.as_str().to_ascii_lowercase();
+    for target in &level.1 {
+        out.push(',');
+        out.push_str(&target.name);
+        out.push('=');
+        out.push_str(&target.level.as_str().to_ascii_lowercase());
+    }
+    out
+}
+
+/// The level of the most specific entry of `targets` matching `target`, i.e. `target`
+/// itself or one of its parents such as `app` for `app::db`.
+#[cfg_attr(not(feature = "log_inner"), allow(dead_code))]
+pub(crate) fn target_level(targets: &[TargetLevel], target: &str) -> Option<LevelFilter> {
+    targets
+        .iter()
+        .filter(|t| {
+            target == t.name
+                || (target.starts_with(&t.name) && target[t.name.len()..].starts_with("::"))
+        })
+        .max_by_key(|t| t.name.len())
+        .map(|t| t.level)
+}
+
 #[allow(clippy::wrong_self_convention)]
 pub trait LevelInto {
     fn into_level(&self) -> &str;
@@ -144,7 +200,7 @@ macro_rules! de_from {
 struct LevelSerde;
 
 impl LevelSerde {
-    fn deserialize<S>(s: S) -> Result<InnerLevel, String>
+    fn deserialize<S>(s: S) -> SimpleResult<InnerLevel>
     where
         S: Into<String>,
     {
@@ -186,6 +242,13 @@ where
     deserializer.deserialize_any(LevelSerde)
 }
 
+pub fn serialize_level<S>(level: &InnerLevel, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&format_level(level))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -201,4 +264,16 @@ mod tests {
         quick_log_level(LevelFilter::Debug);
         quick_log_level(Level::Debug);
     }
+
+    #[test]
+    fn test_format_level() {
+        for input in [
+            "off",
+            "info",
+            "debug,app=error",
+            "warn,app::db=trace,hyper=off",
+        ] {
+            assert_eq!(format_level(&parse_level(input).unwrap()), input);
+        }
+    }
 }
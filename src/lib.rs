@@ -1,14 +1,77 @@
+#![allow(clippy::needless_doctest_main)]
 pub extern crate log;
 
 #[macro_use]
 pub mod macros;
 #[cfg(feature = "log_inner")]
+mod archive;
+#[cfg(feature = "log_inner")]
+mod boost;
+#[cfg(feature = "log_inner")]
+mod compression;
+#[cfg(feature = "log_inner")]
+mod config_file;
+#[cfg(feature = "log_inner")]
+mod env;
+mod error;
+#[cfg(feature = "log_inner")]
+mod filter;
+#[cfg(feature = "log_inner")]
+mod format;
+#[cfg(feature = "log_inner")]
 mod inner;
 pub mod level;
+#[cfg(feature = "log_inner")]
+mod otlp;
+#[cfg(feature = "otlp")]
+mod otlp_export;
 mod out_kind;
+#[cfg(feature = "log_inner")]
+mod output;
+#[cfg(feature = "log_inner")]
+mod partial;
+#[cfg(feature = "log_inner")]
+mod pattern;
+#[cfg(feature = "log_inner")]
+mod reopen;
+#[cfg(feature = "log_inner")]
+mod retention;
+#[cfg(feature = "log_inner")]
+mod rotation;
+#[cfg(feature = "log_inner")]
+mod route;
+#[cfg(all(feature = "signals", unix))]
+mod signals;
+#[cfg(feature = "log_inner")]
+mod watch;
 
+#[cfg(feature = "log_inner")]
+pub use boost::*;
+#[cfg(feature = "log_inner")]
+pub use compression::Compression;
+#[cfg(feature = "log_inner")]
+pub use config_file::*;
+#[cfg(feature = "log_inner")]
+pub use env::*;
+pub use error::SimpleLogError;
+#[cfg(feature = "log_inner")]
+pub use format::{Format, JsonFields, Service};
 #[cfg(feature = "log_inner")]
 pub use inner::*;
+#[cfg(feature = "log_inner")]
+pub use otlp::OtlpOutput;
+#[cfg(feature = "log_inner")]
+pub use output::FileOutput;
+#[cfg(feature = "log_inner")]
+pub use partial::*;
+#[cfg(feature = "log_inner")]
+pub use rotation::{archive_errors, Rotation, RotationPeriod};
+#[cfg(feature = "log_inner")]
+pub use route::Route;
+#[cfg(all(feature = "signals", unix))]
+pub use signals::*;
+#[cfg(feature = "log_inner")]
+pub use watch::*;
 
 pub use log::Level;
 pub use log::LevelFilter;
@@ -17,7 +80,7 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "target")]
 pub use simple_log_derive::*;
 
-pub type SimpleResult<T> = Result<T, String>;
+pub type SimpleResult<T> = Result<T, SimpleLogError>;
 pub(crate) type InnerLevel = (LevelFilter, Vec<TargetLevel>);
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
@@ -26,6 +89,16 @@ pub struct TargetLevel {
     level: LevelFilter,
 }
 
+impl TargetLevel {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_level(&self) -> LevelFilter {
+        self.level
+    }
+}
+
 impl<S> From<(S, LevelFilter)> for TargetLevel
 where
     S: AsRef<str>,
@@ -0,0 +1,152 @@
+//! The `otlp` out kind: records as OpenTelemetry log records, sent to a collector as
+//! OTLP/HTTP JSON. Sending needs the `otlp` feature, `otlp_tls` for `https` endpoints.
+//!
+//! ```toml
+//! out_kind = ["console", "otlp"]
+//!
+//! [otlp]
+//! endpoint = "http://collector:4318/v1/logs"
+//! batch_size = 512
+//! flush_interval = "1s"
+//!
+//! [otlp.headers]
+//! authorization = "Bearer secret"
+//!
+//! [otlp.resource]
+//! "k8s.pod.name" = "api-7d9f"
+//! ```
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Where and how records of the `otlp` out kind are sent.
+///
+/// The resource attributes are `service.name`, `service.version` and `deployment.environment`
+/// from [crate::Service], `host.name`, `process.pid`, and `resource` over those.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct OtlpOutput {
+    /// The OTLP/HTTP logs endpoint.
+    pub endpoint: String,
+    /// HTTP headers of each request, e.g. `authorization`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Extra resource attributes.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub resource: BTreeMap<String, String>,
+    /// Send once this many records are waiting.
+    pub batch_size: usize,
+    /// Send waiting records at least this often.
+    #[serde(
+        serialize_with = "serialize_duration",
+        deserialize_with = "deserialize_duration"
+    )]
+    pub flush_interval: Duration,
+    /// The timeout of a request.
+    #[serde(
+        serialize_with = "serialize_duration",
+        deserialize_with = "deserialize_duration"
+    )]
+    pub timeout: Duration,
+    /// Records are dropped while this many wait to be sent, logging never blocks.
+    pub queue_size: usize,
+}
+
+impl Default for OtlpOutput {
+    fn default() -> Self {
+        OtlpOutput {
+            endpoint: "http://localhost:4318/v1/logs".to_string(),
+            headers: BTreeMap::new(),
+            resource: BTreeMap::new(),
+            batch_size: 512,
+            flush_interval: Duration::from_secs(1),
+            timeout: Duration::from_secs(10),
+            queue_size: 2048,
+        }
+    }
+}
+
+impl OtlpOutput {
+    pub fn new<S: Into<String>>(endpoint: S) -> OtlpOutput {
+        OtlpOutput {
+            endpoint: endpoint.into(),
+            ..OtlpOutput::default()
+        }
+    }
+
+    pub fn header<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> OtlpOutput {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn resource<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> OtlpOutput {
+        self.resource.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn batch_size(mut self, batch_size: usize) -> OtlpOutput {
+        self.batch_size = batch_size;
+        self
+    }
+
+    pub fn flush_interval(mut self, flush_interval: Duration) -> OtlpOutput {
+        self.flush_interval = flush_interval;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> OtlpOutput {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn queue_size(mut self, queue_size: usize) -> OtlpOutput {
+        self.queue_size = queue_size;
+        self
+    }
+
+    pub(crate) fn is_default(&self) -> bool {
+        *self == OtlpOutput::default()
+    }
+}
+
+fn serialize_duration<S: Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&humantime::format_duration(*duration))
+}
+
+/// Deserialize a human readable duration, e.g. `"500ms"` or `"2s"`.
+fn deserialize_duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    humantime::parse_duration(s.trim()).map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_otlp_output_serde() {
+        let toml = r#"
+            endpoint = "http://collector:4318/v1/logs"
+            flush_interval = "500ms"
+
+            [headers]
+            authorization = "Bearer secret"
+        "#;
+        let otlp: OtlpOutput = toml::from_str(toml).unwrap();
+        assert_eq!(
+            otlp,
+            OtlpOutput::new("http://collector:4318/v1/logs")
+                .header("authorization", "Bearer secret")
+                .flush_interval(Duration::from_millis(500))
+        );
+        let json = serde_json::to_string(&otlp).unwrap();
+        assert_eq!(
+            json,
+            r#"{"endpoint":"http://collector:4318/v1/logs","headers":{"authorization":"Bearer secret"},"batch_size":512,"flush_interval":"500ms","timeout":"10s","queue_size":2048}"#
+        );
+    }
+}
@@ -0,0 +1,615 @@
+//! Map records to the OpenTelemetry log data model and send them as OTLP/HTTP JSON.
+//!
+//! The appender only queues records, a background thread sends them in batches, see
+//! [crate::OtlpOutput]. Call `log::logger().flush()` before exit to send the last batch.
+
+use crate::archive::hostname;
+use crate::format::{executable_name, key_values};
+use crate::otlp::OtlpOutput;
+use crate::{Service, SimpleLogError, SimpleResult};
+use log::{Level, Record};
+use log4rs::append::Append;
+use serde_json::{json, Value};
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// The key-values that set the trace context of a record, as hex ids.
+const TRACE_ID: &str = "trace_id";
+const SPAN_ID: &str = "span_id";
+
+/// How often a flush retries to queue on a full queue.
+const FLUSH_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
+thread_local! {
+    /// Set on the exporter thread, whose own records, e.g. from the HTTP client, are ignored.
+    static EXPORTER_THREAD: Cell<bool> = const { Cell::new(false) };
+}
+
+/// The OTel severity number of `level`, the lowest of its range.
+fn severity_number(level: Level) -> u8 {
+    match level {
+        Level::Trace => 1,
+        Level::Debug => 5,
+        Level::Info => 9,
+        Level::Warn => 13,
+        Level::Error => 17,
+    }
+}
+
+/// An OTLP `AnyValue`.
+fn any_value(value: Value) -> Value {
+    match value {
+        Value::Null => json!({}),
+        Value::Bool(value) => json!({ "boolValue": value }),
+        // int64 is a string in OTLP JSON
+        Value::Number(number) => match number.as_i64() {
+            Some(value) => json!({ "intValue": value.to_string() }),
+            None => json!({ "doubleValue": number.as_f64() }),
+        },
+        Value::String(value) => json!({ "stringValue": value }),
+        value => json!({ "stringValue": value.to_string() }),
+    }
+}
+
+fn attribute(key: &str, value: Value) -> Value {
+    json!({ "key": key, "value": any_value(value) })
+}
+
+/// `value` as a lowercase id of `len` hex digits, `None` if it is not a valid id.
+fn hex_id(value: &Value, len: usize) -> Option<String> {
+    let id = value.as_str()?;
+    let valid = id.len() == len
+        && id.chars().all(|ch| ch.is_ascii_hexdigit())
+        && id.chars().any(|ch| ch != '0');
+    valid.then(|| id.to_ascii_lowercase())
+}
+
+/// `record` as an OTLP `LogRecord`.
+///
+/// Key-values become attributes, next to the code location and thread name, except valid
+/// `trace_id` and `span_id` values, which set the trace context.
+pub(crate) fn log_record(record: &Record, thread: Option<&str>, time_unix_nano: u128) -> Value {
+    let time = time_unix_nano.to_string();
+    let mut log_record = json!({
+        "timeUnixNano": time,
+        "observedTimeUnixNano": time,
+        "severityNumber": severity_number(record.level()),
+        "severityText": record.level().as_str(),
+        "body": { "stringValue": record.args().to_string() },
+    });
+    let mut attributes = vec![];
+    for (key, value) in key_values(record) {
+        let id = match key.as_str() {
+            TRACE_ID => hex_id(&value, 32).map(|id| ("traceId", id)),
+            SPAN_ID => hex_id(&value, 16).map(|id| ("spanId", id)),
+            _ => None,
+        };
+        match id {
+            Some((field, id)) => log_record[field] = id.into(),
+            None => attributes.push(attribute(&key, value)),
+        }
+    }
+    if let Some(module) = record.module_path() {
+        attributes.push(attribute("code.namespace", module.into()));
+    }
+    if let Some(file) = record.file() {
+        attributes.push(attribute("code.filepath", file.into()));
+    }
+    if let Some(line) = record.line() {
+        attributes.push(attribute("code.lineno", line.into()));
+    }
+    if let Some(thread) = thread {
+        attributes.push(attribute("thread.name", thread.into()));
+    }
+    if !attributes.is_empty() {
+        log_record["attributes"] = attributes.into();
+    }
+    log_record
+}
+
+/// The resource attributes of [OtlpOutput].
+fn resource(otlp: &OtlpOutput, service: &Service) -> Vec<Value> {
+    let mut attributes = BTreeMap::new();
+    if let Some(name) = service.name.clone().or_else(executable_name) {
+        attributes.insert("service.name".to_string(), Value::from(name));
+    }
+    if let Some(version) = &service.version {
+        attributes.insert("service.version".to_string(), version.as_str().into());
+    }
+    if let Some(environment) = &service.environment {
+        attributes.insert(
+            "deployment.environment".to_string(),
+            environment.as_str().into(),
+        );
+    }
+    attributes.insert("host.name".to_string(), hostname().into());
+    attributes.insert("process.pid".to_string(), std::process::id().into());
+    for (key, value) in &otlp.resource {
+        attributes.insert(key.clone(), value.as_str().into());
+    }
+    attributes
+        .into_iter()
+        .map(|(key, value)| attribute(&key, value))
+        .collect()
+}
+
+/// A log record and the target it is scoped by.
+#[derive(Debug)]
+struct Entry {
+    target: String,
+    log_record: Value,
+}
+
+#[derive(Debug)]
+enum Message {
+    Entry(Entry),
+    /// Send the waiting records, then signal done.
+    Flush(mpsc::Sender<()>),
+}
+
+/// The queue of the exporter thread, which stops once the last clone is dropped.
+///
+/// It is kept across config updates, and reused while the settings it was started with
+/// are unchanged.
+#[derive(Debug, Clone)]
+pub(crate) struct OtlpExporter {
+    sender: SyncSender<Message>,
+    dropped: Arc<AtomicUsize>,
+    otlp: OtlpOutput,
+    service: Service,
+}
+
+impl OtlpExporter {
+    pub(crate) fn new(otlp: &OtlpOutput, service: &Service) -> SimpleResult<OtlpExporter> {
+        let invalid = |message: &str| {
+            Err(SimpleLogError::Config(format!(
+                "otlp endpoint '{}' {}",
+                otlp.endpoint, message
+            )))
+        };
+        match otlp.endpoint.parse::<ureq::http::Uri>() {
+            Ok(uri) if uri.host().is_none() => return invalid("has no host"),
+            Ok(uri) => match uri.scheme_str() {
+                Some("http") => {}
+                Some("https") if cfg!(feature = "otlp_tls") => {}
+                Some("https") => return invalid("needs the `otlp_tls` feature"),
+                _ => return invalid("is not an http or https url"),
+            },
+            Err(e) => return invalid(&format!("is not a url: {}", e)),
+        }
+        if otlp.batch_size == 0 || otlp.queue_size == 0 {
+            return Err(SimpleLogError::Config(
+                "otlp batch_size and queue_size must be at least 1".to_string(),
+            ));
+        }
+
+        let (sender, receiver) = mpsc::sync_channel(otlp.queue_size);
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let agent = ureq::Agent::config_builder()
+            .timeout_global(Some(otlp.timeout))
+            .build()
+            .into();
+        let worker = Worker {
+            agent,
+            otlp: otlp.clone(),
+            resource: resource(otlp, service),
+            dropped: dropped.clone(),
+        };
+        thread::Builder::new()
+            .name("simple-log-otlp".to_string())
+            .spawn(move || worker.run(receiver))
+            .map_err(|e| {
+                SimpleLogError::Config(format!("failed to start the otlp exporter: {}", e))
+            })?;
+        Ok(OtlpExporter {
+            sender,
+            dropped,
+            otlp: otlp.clone(),
+            service: service.clone(),
+        })
+    }
+
+    /// Whether it was started with `otlp` and `service`.
+    pub(crate) fn is_for(&self, otlp: &OtlpOutput, service: &Service) -> bool {
+        self.otlp == *otlp && self.service == *service
+    }
+
+    /// Whether `other` is a clone of it, with the same exporter thread.
+    #[cfg(test)]
+    pub(crate) fn is_clone_of(&self, other: &OtlpExporter) -> bool {
+        Arc::ptr_eq(&self.dropped, &other.dropped)
+    }
+}
+
+/// Queues records for an [OtlpExporter].
+#[derive(Debug)]
+pub(crate) struct OtlpAppender(OtlpExporter);
+
+impl OtlpAppender {
+    pub(crate) fn new(exporter: OtlpExporter) -> OtlpAppender {
+        OtlpAppender(exporter)
+    }
+}
+
+impl Append for OtlpAppender {
+    fn append(&self, record: &Record) -> anyhow::Result<()> {
+        if EXPORTER_THREAD.with(Cell::get) {
+            return Ok(());
+        }
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let entry = Entry {
+            target: record.target().to_string(),
+            log_record: log_record(record, thread::current().name(), time),
+        };
+        match self.0.sender.try_send(Message::Entry(entry)) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => {
+                self.0.dropped.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(TrySendError::Disconnected(_)) => Err(anyhow::anyhow!("otlp exporter stopped")),
+        }
+    }
+
+    fn flush(&self) {
+        if EXPORTER_THREAD.with(Cell::get) {
+            return;
+        }
+        // a batch may be on its way already
+        let deadline = Instant::now() + self.0.otlp.timeout * 2;
+        let (done, wait) = mpsc::channel();
+        let mut message = Message::Flush(done);
+        // never block on a full queue past the deadline
+        loop {
+            match self.0.sender.try_send(message) {
+                Ok(()) => break,
+                Err(TrySendError::Full(full)) if Instant::now() < deadline => {
+                    message = full;
+                    thread::sleep(FLUSH_RETRY_INTERVAL);
+                }
+                Err(_) => return,
+            }
+        }
+        let _ = wait.recv_timeout(deadline.saturating_duration_since(Instant::now()));
+    }
+}
+
+/// Sends the queued records, on the exporter thread.
+struct Worker {
+    agent: ureq::Agent,
+    otlp: OtlpOutput,
+    resource: Vec<Value>,
+    dropped: Arc<AtomicUsize>,
+}
+
+impl Worker {
+    fn run(self, receiver: Receiver<Message>) {
+        EXPORTER_THREAD.with(|exporter| exporter.set(true));
+        let mut batch = vec![];
+        // when the oldest waiting record is due
+        let mut due: Option<Instant> = None;
+        loop {
+            let message = match due {
+                Some(due) => receiver.recv_timeout(due.saturating_duration_since(Instant::now())),
+                None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match message {
+                Ok(Message::Entry(entry)) => {
+                    batch.push(entry);
+                    due.get_or_insert_with(|| Instant::now() + self.otlp.flush_interval);
+                    if batch.len() < self.otlp.batch_size {
+                        continue;
+                    }
+                }
+                Ok(Message::Flush(done)) => {
+                    self.export(&mut batch);
+                    due = None;
+                    let _ = done.send(());
+                    continue;
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.export(&mut batch);
+                    return;
+                }
+            }
+            self.export(&mut batch);
+            due = None;
+        }
+    }
+
+    fn export(&self, batch: &mut Vec<Entry>) {
+        let dropped = self.dropped.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            log::error!(
+                target: "simple_log",
+                "Dropped {} log records, the otlp queue is full",
+                dropped
+            );
+        }
+        if batch.is_empty() {
+            return;
+        }
+        let count = batch.len();
+        let body = self.body(batch.drain(..));
+        if let Err(e) = self.post(&body) {
+            // the exporter thread ignores its own records, they go to the other outputs
+            log::error!(
+                target: "simple_log",
+                "Failed to export {} log records to {}: {}",
+                count,
+                self.otlp.endpoint,
+                e
+            );
+        }
+    }
+
+    /// An `ExportLogsServiceRequest`, with a scope per target.
+    fn body(&self, entries: impl Iterator<Item = Entry>) -> Vec<u8> {
+        let mut scopes: Vec<(String, Vec<Value>)> = vec![];
+        for entry in entries {
+            match scopes
+                .iter_mut()
+                .find(|(target, _)| *target == entry.target)
+            {
+                Some((_, log_records)) => log_records.push(entry.log_record),
+                None => scopes.push((entry.target, vec![entry.log_record])),
+            }
+        }
+        let scope_logs: Vec<Value> = scopes
+            .into_iter()
+            .map(|(target, log_records)| {
+                json!({ "scope": { "name": target }, "logRecords": log_records })
+            })
+            .collect();
+        let request = json!({
+            "resourceLogs": [{
+                "resource": { "attributes": self.resource },
+                "scopeLogs": scope_logs,
+            }]
+        });
+        request.to_string().into_bytes()
+    }
+
+    fn post(&self, body: &[u8]) -> Result<(), ureq::Error> {
+        let mut request = self
+            .agent
+            .post(&self.otlp.endpoint)
+            .content_type("application/json");
+        for (name, value) in &self.otlp.headers {
+            request = request.header(name, value);
+        }
+        request.send(body)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+
+    /// A request received by [collector]: lowercase header lines and the JSON body.
+    type Request = (Vec<String>, Value);
+
+    /// A stand-in OTLP/HTTP collector on a local port, answering every request with 200.
+    fn collector() -> (String, Receiver<Request>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/v1/logs", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let sender = sender.clone();
+                thread::spawn(move || serve(stream.unwrap(), sender));
+            }
+        });
+        (endpoint, receiver)
+    }
+
+    fn serve(stream: TcpStream, sender: mpsc::Sender<Request>) {
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut stream = stream;
+        loop {
+            let mut headers = vec![];
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                    return;
+                }
+                let line = line.trim_end().to_ascii_lowercase();
+                if line.is_empty() {
+                    break;
+                }
+                headers.push(line);
+            }
+            let length = headers
+                .iter()
+                .find_map(|line| line.strip_prefix("content-length:"))
+                .map_or(0, |length| length.trim().parse().unwrap());
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: 2\r\n\r\n{}")
+                .unwrap();
+            let _ = sender.send((headers, serde_json::from_slice(&body).unwrap()));
+        }
+    }
+
+    fn append(appender: &OtlpAppender, target: &str, message: &str) {
+        appender
+            .append(
+                &Record::builder()
+                    .level(Level::Info)
+                    .target(target)
+                    .args(format_args!("{}", message))
+                    .build(),
+            )
+            .unwrap();
+    }
+
+    fn receive(receiver: &Receiver<Request>) -> Request {
+        receiver.recv_timeout(Duration::from_secs(10)).unwrap()
+    }
+
+    fn messages(request: &Value) -> Vec<(String, String)> {
+        let mut messages = vec![];
+        for scope in request["resourceLogs"][0]["scopeLogs"].as_array().unwrap() {
+            for log_record in scope["logRecords"].as_array().unwrap() {
+                messages.push((
+                    scope["scope"]["name"].as_str().unwrap().to_string(),
+                    log_record["body"]["stringValue"]
+                        .as_str()
+                        .unwrap()
+                        .to_string(),
+                ));
+            }
+        }
+        messages
+    }
+
+    #[test]
+    fn test_log_record() {
+        let key_values = [
+            ("user", log::kv::Value::from("alice")),
+            ("attempts", log::kv::Value::from(2u64)),
+            ("ratio", log::kv::Value::from(0.5)),
+            (
+                "trace_id",
+                log::kv::Value::from("4BF92F3577B34DA6A3CE929D0E0E4736"),
+            ),
+            ("span_id", log::kv::Value::from("not-an-id")),
+        ];
+        let record = Record::builder()
+            .level(Level::Warn)
+            .target("app::db")
+            .module_path(Some("app::db"))
+            .file(Some("src/db.rs"))
+            .line(Some(7))
+            .args(format_args!("slow query"))
+            .key_values(&key_values)
+            .build();
+        let log_record = log_record(&record, Some("worker-1"), 1_709_269_509_000_000_123);
+        assert_eq!(
+            log_record,
+            json!({
+                "timeUnixNano": "1709269509000000123",
+                "observedTimeUnixNano": "1709269509000000123",
+                "severityNumber": 13,
+                "severityText": "WARN",
+                "body": { "stringValue": "slow query" },
+                "traceId": "4bf92f3577b34da6a3ce929d0e0e4736",
+                "attributes": [
+                    { "key": "user", "value": { "stringValue": "alice" } },
+                    { "key": "attempts", "value": { "intValue": "2" } },
+                    { "key": "ratio", "value": { "doubleValue": 0.5 } },
+                    { "key": "span_id", "value": { "stringValue": "not-an-id" } },
+                    { "key": "code.namespace", "value": { "stringValue": "app::db" } },
+                    { "key": "code.filepath", "value": { "stringValue": "src/db.rs" } },
+                    { "key": "code.lineno", "value": { "intValue": "7" } },
+                    { "key": "thread.name", "value": { "stringValue": "worker-1" } },
+                ],
+            })
+        );
+        assert_eq!(severity_number(Level::Error), 17);
+        assert_eq!(hex_id(&json!("00000000000000000000000000000000"), 32), None);
+    }
+
+    #[test]
+    fn test_export_batches() {
+        let (endpoint, requests) = collector();
+        let otlp = OtlpOutput::new(endpoint)
+            .header("authorization", "Bearer secret")
+            .resource("k8s.pod.name", "api-7d9f")
+            .batch_size(2)
+            .flush_interval(Duration::from_secs(3600));
+        let service = Service::new("api").version("1.4.2");
+        let appender = OtlpAppender::new(OtlpExporter::new(&otlp, &service).unwrap());
+        append(&appender, "app", "one");
+        append(&appender, "app::db", "two");
+        append(&appender, "app", "three");
+
+        // a full batch is sent right away
+        let (headers, request) = receive(&requests);
+        assert!(headers.starts_with(&["post /v1/logs http/1.1".to_string()]));
+        assert!(headers.contains(&"authorization: bearer secret".to_string()));
+        assert!(headers.contains(&"content-type: application/json".to_string()));
+        assert_eq!(
+            messages(&request),
+            [("app", "one"), ("app::db", "two")].map(|(t, m)| (t.to_string(), m.to_string()))
+        );
+        let attributes = request["resourceLogs"][0]["resource"]["attributes"]
+            .as_array()
+            .unwrap();
+        for (key, value) in [
+            ("service.name", json!({ "stringValue": "api" })),
+            ("service.version", json!({ "stringValue": "1.4.2" })),
+            ("k8s.pod.name", json!({ "stringValue": "api-7d9f" })),
+            (
+                "process.pid",
+                json!({ "intValue": std::process::id().to_string() }),
+            ),
+        ] {
+            assert!(
+                attributes.contains(&json!({ "key": key, "value": value })),
+                "{}",
+                key
+            );
+        }
+
+        // the rest waits for a flush
+        assert!(requests.recv_timeout(Duration::from_millis(200)).is_err());
+        appender.flush();
+        let (_, request) = receive(&requests);
+        assert_eq!(
+            messages(&request),
+            [("app".to_string(), "three".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_export_interval_and_drop() {
+        let (endpoint, requests) = collector();
+        let otlp = OtlpOutput::new(endpoint).flush_interval(Duration::from_millis(50));
+        let appender = OtlpAppender::new(OtlpExporter::new(&otlp, &Service::default()).unwrap());
+        append(&appender, "app", "due");
+        let (_, request) = receive(&requests);
+        assert_eq!(messages(&request), [("app".to_string(), "due".to_string())]);
+
+        // dropping the appender sends what is left
+        let otlp = otlp.flush_interval(Duration::from_secs(3600));
+        let appender = OtlpAppender::new(OtlpExporter::new(&otlp, &Service::default()).unwrap());
+        append(&appender, "app", "last");
+        drop(appender);
+        let (_, request) = receive(&requests);
+        assert_eq!(
+            messages(&request),
+            [("app".to_string(), "last".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_invalid_endpoint() {
+        for (endpoint, message) in [
+            ("localhost:4318", "is not an http or https url"),
+            ("ftp://collector/v1/logs", "is not an http or https url"),
+            ("/v1/logs", "has no host"),
+        ] {
+            let err = OtlpExporter::new(&OtlpOutput::new(endpoint), &Service::default())
+                .unwrap_err()
+                .to_string();
+            assert!(err.ends_with(message), "{}: {}", endpoint, err);
+        }
+        let err = OtlpExporter::new(&OtlpOutput::default().batch_size(0), &Service::default())
+            .unwrap_err();
+        assert!(err.to_string().contains("batch_size"));
+    }
+}
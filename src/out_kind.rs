@@ -1,6 +1,9 @@
+use crate::{SimpleLogError, SimpleResult};
 use core::fmt;
 use serde::de::SeqAccess;
-use serde::{de, Deserialize, Deserializer, Serialize};
+use serde::ser::SerializeSeq;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::str::FromStr;
 
 macro_rules! de_from {
     ($err:expr) => {
@@ -10,11 +13,32 @@ macro_rules! de_from {
 
 const KIND_FILE: &str = "file";
 const KIND_CONSOLE: &str = "console";
+const KIND_OTLP: &str = "otlp";
 
 #[derive(Debug, Serialize, PartialEq, Eq, Clone)]
 pub enum OutKind {
     File,
     Console,
+    /// OpenTelemetry log records sent to a collector, see `OtlpOutput`.
+    Otlp,
+}
+
+impl OutKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutKind::File => KIND_FILE,
+            OutKind::Console => KIND_CONSOLE,
+            OutKind::Otlp => KIND_OTLP,
+        }
+    }
+}
+
+impl FromStr for OutKind {
+    type Err = SimpleLogError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        KindSerde::deserialize(s.trim())
+    }
 }
 
 impl<'de> Deserialize<'de> for OutKind {
@@ -28,7 +52,8 @@ impl<'de> Deserialize<'de> for OutKind {
     }
 }
 
-const KIND_EXPECT: &str = "expect out_kind string or vec:'console','file' or ['console','file']";
+const KIND_EXPECT: &str =
+    "expect out_kind string or vec:'console','file','otlp' or ['console','file','otlp']";
 
 impl<S: AsRef<str>> From<S> for OutKind {
     fn from(value: S) -> Self {
@@ -38,7 +63,7 @@ impl<S: AsRef<str>> From<S> for OutKind {
 pub struct KindSerde;
 
 impl KindSerde {
-    fn deserialize<S>(s: S) -> Result<OutKind, String>
+    fn deserialize<S>(s: S) -> SimpleResult<OutKind>
     where
         S: Into<String>,
     {
@@ -46,7 +71,11 @@ impl KindSerde {
         match s.to_ascii_lowercase().as_str() {
             KIND_FILE => Ok(OutKind::File),
             KIND_CONSOLE => Ok(OutKind::Console),
-            _ => Err(format!("Invalid state '{}',{}", s, KIND_EXPECT)),
+            KIND_OTLP => Ok(OutKind::Otlp),
+            _ => Err(SimpleLogError::InvalidOutKind(format!(
+                "Invalid state '{}',{}",
+                s, KIND_EXPECT
+            ))),
         }
     }
 }
@@ -81,6 +110,17 @@ where
     deserializer.deserialize_any(KindSerde)
 }
 
+pub(crate) fn serialize_out_kind<S>(kinds: &[OutKind], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut seq = serializer.serialize_seq(Some(kinds.len()))?;
+    for kind in kinds {
+        seq.serialize_element(kind.as_str())?;
+    }
+    seq.end()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -99,6 +139,10 @@ mod tests {
         let kind: Vec<OutKind> = serde_json::from_str(json).unwrap();
         assert_eq!(kind, vec![OutKind::Console, OutKind::File]);
 
+        let json = r#"["OTLP"]"#;
+        let kind: Vec<OutKind> = serde_json::from_str(json).unwrap();
+        assert_eq!(kind, vec![OutKind::Otlp]);
+
         let json = r#"["CONSOLE", "FILE"]"#;
         let kind: Vec<OutKind> = serde_json::from_str(json).unwrap();
         assert_eq!(kind, vec![OutKind::Console, OutKind::File]);
@@ -0,0 +1,118 @@
+//! Named file outputs, written next to the `path` output.
+//!
+//! ```toml
+//! out_kind = ["console", "file"]
+//! path = "app.log"
+//!
+//! [[files]]
+//! name = "error"
+//! path = "error.log"
+//!
+//! [[files]]
+//! name = "audit"
+//! path = "audit.log"
+//! size = 500
+//! roll_count = 50
+//! pattern = "{d} {m}{n}"
+//!
+//! # the level of an output is set in `output_levels`, by its name
+//! [output_levels]
+//! error = "warn"
+//! ```
+
+use crate::format::Format;
+use serde::{Deserialize, Serialize};
+
+/// A named file output with its own path, size, roll count and pattern.
+///
+/// Its level is set in [crate::LogConfig] `output_levels`, like that of every output.
+///
+/// Unset values are taken from [crate::LogConfig], as are `directory`, `rotation`, the
+/// retention settings, `compression`, `roll_on_startup` and `external_rotation`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct FileOutput {
+    /// The appender name, unique across outputs.
+    pub name: String,
+    /// The log file, relative to `directory` when that is set.
+    pub path: String,
+    /// Roll the file at this size(MB).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub roll_count: Option<u32>,
+    /// A log4rs pattern, e.g. `{d} [{l}] {m}{n}`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// Text or JSON, see [Format].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<Format>,
+    /// Archive file name pattern, archives are named after `path` if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive_pattern: Option<String>,
+    /// Only receive the records routed to it, see [crate::Route].
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub routed_only: bool,
+}
+
+impl FileOutput {
+    pub fn new<N: Into<String>, P: Into<String>>(name: N, path: P) -> FileOutput {
+        FileOutput {
+            name: name.into(),
+            path: path.into(),
+            size: None,
+            roll_count: None,
+            pattern: None,
+            format: None,
+            archive_pattern: None,
+            routed_only: false,
+        }
+    }
+
+    pub fn size(mut self, size: u64) -> FileOutput {
+        self.size = Some(size);
+        self
+    }
+
+    pub fn roll_count(mut self, roll_count: u32) -> FileOutput {
+        self.roll_count = Some(roll_count);
+        self
+    }
+
+    pub fn pattern<S: Into<String>>(mut self, pattern: S) -> FileOutput {
+        self.pattern = Some(pattern.into());
+        self
+    }
+
+    pub fn format(mut self, format: Format) -> FileOutput {
+        self.format = Some(format);
+        self
+    }
+
+    pub fn archive_pattern<S: Into<String>>(mut self, archive_pattern: S) -> FileOutput {
+        self.archive_pattern = Some(archive_pattern.into());
+        self
+    }
+
+    pub fn routed_only(mut self, routed_only: bool) -> FileOutput {
+        self.routed_only = routed_only;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_output_serde() {
+        let toml = r#"
+            name = "error"
+            path = "error.log"
+            size = 5
+        "#;
+        let output: FileOutput = toml::from_str(toml).unwrap();
+        assert_eq!(output, FileOutput::new("error", "error.log").size(5));
+        let json = serde_json::to_string(&output).unwrap();
+        assert_eq!(json, r#"{"name":"error","path":"error.log","size":5}"#);
+    }
+}
@@ -0,0 +1,456 @@
+//! Layered [LogConfig]: merge partial configs in priority order.
+//!
+//! A typical stack is defaults < base file < site file < env < code,
+//! every layer only sets the fields it cares about.
+
+use crate::compression::Compression;
+use crate::config_file::read_config;
+use crate::filter::deserialize_output_levels;
+use crate::format::{Format, JsonFields, Service};
+use crate::level::deserialize_level;
+use crate::otlp::OtlpOutput;
+use crate::out_kind::{deserialize_out_kind, OutKind};
+use crate::output::FileOutput;
+use crate::retention::deserialize_max_age;
+use crate::rotation::Rotation;
+use crate::route::Route;
+use crate::{InnerLevel, LogConfig, SimpleResult};
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Where an effective [LogConfig] value came from.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ConfigSource {
+    /// simple-log default value.
+    Default,
+    /// Loaded from a config file.
+    File(PathBuf),
+    /// Read from the environment variables with this prefix.
+    Env(String),
+    /// Set in code, such as CLI flags.
+    #[default]
+    Code,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => f.write_str("default"),
+            ConfigSource::File(path) => write!(f, "file {}", path.display()),
+            ConfigSource::Env(prefix) => write!(f, "env {}_*", prefix),
+            ConfigSource::Code => f.write_str("code"),
+        }
+    }
+}
+
+/// The source of each [LogConfig] field, keyed by field name.
+pub type ConfigSources = BTreeMap<&'static str, ConfigSource>;
+
+/// The [ConfigSources] of a [LogConfig]. They describe how the config was loaded, not
+/// the config itself, so they are equal to any other sources.
+///
+/// Read them with [LogConfig::get_sources].
+#[doc(hidden)]
+#[derive(Debug, Clone, Default)]
+pub struct FieldSources(ConfigSources);
+
+impl PartialEq for FieldSources {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for FieldSources {}
+
+impl Deref for FieldSources {
+    type Target = ConfigSources;
+
+    fn deref(&self) -> &ConfigSources {
+        &self.0
+    }
+}
+
+impl DerefMut for FieldSources {
+    fn deref_mut(&mut self) -> &mut ConfigSources {
+        &mut self.0
+    }
+}
+
+/// A [LogConfig] layer where every field is optional.
+///
+/// # Examples
+///
+/// ```rust
+/// use simple_log::{ConfigSource, LogConfig, PartialLogConfig};
+///
+/// let base = PartialLogConfig {
+///     size: Some(100),
+///     time_format: Some("%H:%M:%S.%f".to_string()),
+///     ..PartialLogConfig::default()
+/// }
+/// .with_source(ConfigSource::File("/etc/app/log.toml".into()));
+///
+/// let cli = PartialLogConfig {
+///     size: Some(20),
+///     ..PartialLogConfig::default()
+/// };
+///
+/// let config: LogConfig = PartialLogConfig::merge([base, cli]);
+/// assert_eq!(config.get_size(), 20);
+/// assert_eq!(config.get_source("size"), Some(&ConfigSource::Code));
+/// assert_eq!(
+///     config.get_source("time_format"),
+///     Some(&ConfigSource::File("/etc/app/log.toml".into()))
+/// );
+/// assert_eq!(config.get_source("level"), Some(&ConfigSource::Default));
+/// ```
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct PartialLogConfig {
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub directory: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_opt_level")]
+    pub level: Option<InnerLevel>,
+    #[serde(default)]
+    pub size: Option<u64>,
+    #[serde(default, deserialize_with = "deserialize_opt_out_kind")]
+    pub out_kind: Option<Vec<OutKind>>,
+    #[serde(default)]
+    pub roll_count: Option<u32>,
+    #[serde(default)]
+    pub time_format: Option<String>,
+    #[serde(default)]
+    pub rotation: Option<Rotation>,
+    #[serde(default, deserialize_with = "deserialize_max_age")]
+    pub max_age: Option<Duration>,
+    #[serde(default)]
+    pub max_total_size: Option<u64>,
+    #[serde(default)]
+    pub archive_pattern: Option<String>,
+    #[serde(default)]
+    pub compression: Option<Compression>,
+    #[serde(default)]
+    pub roll_on_startup: Option<bool>,
+    #[serde(default)]
+    pub current_link: Option<String>,
+    #[serde(default)]
+    pub external_rotation: Option<bool>,
+    #[serde(default)]
+    pub files: Option<Vec<FileOutput>>,
+    #[serde(default)]
+    pub routes: Option<Vec<Route>>,
+    #[serde(default, deserialize_with = "deserialize_opt_output_levels")]
+    pub output_levels: Option<BTreeMap<String, InnerLevel>>,
+    #[serde(default)]
+    pub pattern: Option<String>,
+    #[serde(default)]
+    pub output_patterns: Option<BTreeMap<String, String>>,
+    #[serde(default)]
+    pub format: Option<Format>,
+    #[serde(default)]
+    pub output_formats: Option<BTreeMap<String, Format>>,
+    #[serde(default)]
+    pub json_fields: Option<JsonFields>,
+    #[serde(default)]
+    pub service: Option<Service>,
+    #[serde(default)]
+    pub otlp: Option<OtlpOutput>,
+    /// Where the values of this layer come from, [ConfigSource::Code] by default.
+    #[serde(skip)]
+    pub source: ConfigSource,
+}
+
+fn deserialize_opt_level<'de, D>(deserializer: D) -> Result<Option<InnerLevel>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_level(deserializer).map(Some)
+}
+
+fn deserialize_opt_out_kind<'de, D>(deserializer: D) -> Result<Option<Vec<OutKind>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_out_kind(deserializer).map(Some)
+}
+
+fn deserialize_opt_output_levels<'de, D>(
+    deserializer: D,
+) -> Result<Option<BTreeMap<String, InnerLevel>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_output_levels(deserializer).map(Some)
+}
+
+impl PartialLogConfig {
+    /// Load a layer from a TOML, YAML or JSON file, see [LogConfig::from_path].
+    pub fn from_path<P: AsRef<Path>>(path: P) -> SimpleResult<PartialLogConfig> {
+        let path = path.as_ref();
+        let layer: PartialLogConfig = read_config(path)?;
+        Ok(layer.with_source(ConfigSource::File(path.to_path_buf())))
+    }
+
+    /// Set where the values of this layer come from.
+    pub fn with_source(mut self, source: ConfigSource) -> PartialLogConfig {
+        self.source = source;
+        self
+    }
+
+    /// Merge layers in priority order, later layers override earlier ones.
+    ///
+    /// Fields that no layer sets keep the [LogConfig] default value.
+    pub fn merge<I>(layers: I) -> LogConfig
+    where
+        I: IntoIterator<Item = PartialLogConfig>,
+    {
+        let mut config = LogConfig::default();
+        for field in LogConfig::FIELDS {
+            config.sources.insert(field, ConfigSource::Default);
+        }
+        for layer in layers {
+            config.apply(layer);
+        }
+        config
+    }
+}
+
+impl LogConfig {
+    pub(crate) const FIELDS: [&'static str; 25] = [
+        "path",
+        "directory",
+        "level",
+        "size",
+        "out_kind",
+        "roll_count",
+        "time_format",
+        "rotation",
+        "max_age",
+        "max_total_size",
+        "archive_pattern",
+        "compression",
+        "roll_on_startup",
+        "current_link",
+        "external_rotation",
+        "files",
+        "routes",
+        "output_levels",
+        "pattern",
+        "output_patterns",
+        "format",
+        "output_formats",
+        "json_fields",
+        "service",
+        "otlp",
+    ];
+
+    /// Override the fields set in `layer`, and record their [ConfigSource].
+    pub fn apply(&mut self, layer: PartialLogConfig) {
+        let source = layer.source;
+        let mut set = |field: &'static str| {
+            self.sources.insert(field, source.clone());
+        };
+        if let Some(path) = layer.path {
+            self.path = Some(path);
+            set("path");
+        }
+        if let Some(directory) = layer.directory {
+            self.directory = Some(directory);
+            set("directory");
+        }
+        if let Some(level) = layer.level {
+            self.level = level;
+            set("level");
+        }
+        if let Some(size) = layer.size {
+            self.size = size;
+            set("size");
+        }
+        if let Some(out_kind) = layer.out_kind {
+            self.out_kind = out_kind;
+            set("out_kind");
+        }
+        if let Some(roll_count) = layer.roll_count {
+            self.roll_count = roll_count;
+            set("roll_count");
+        }
+        if let Some(time_format) = layer.time_format {
+            self.time_format = Some(time_format);
+            set("time_format");
+        }
+        if let Some(rotation) = layer.rotation {
+            self.rotation = rotation;
+            set("rotation");
+        }
+        if let Some(max_age) = layer.max_age {
+            self.max_age = Some(max_age);
+            set("max_age");
+        }
+        if let Some(max_total_size) = layer.max_total_size {
+            self.max_total_size = Some(max_total_size);
+            set("max_total_size");
+        }
+        if let Some(archive_pattern) = layer.archive_pattern {
+            self.archive_pattern = Some(archive_pattern);
+            set("archive_pattern");
+        }
+        if let Some(compression) = layer.compression {
+            self.compression = compression;
+            set("compression");
+        }
+        if let Some(roll_on_startup) = layer.roll_on_startup {
+            self.roll_on_startup = roll_on_startup;
+            set("roll_on_startup");
+        }
+        if let Some(current_link) = layer.current_link {
+            self.current_link = Some(current_link);
+            set("current_link");
+        }
+        if let Some(external_rotation) = layer.external_rotation {
+            self.external_rotation = external_rotation;
+            set("external_rotation");
+        }
+        if let Some(files) = layer.files {
+            self.files = files;
+            set("files");
+        }
+        if let Some(routes) = layer.routes {
+            self.routes = routes;
+            set("routes");
+        }
+        if let Some(output_levels) = layer.output_levels {
+            self.output_levels = output_levels;
+            set("output_levels");
+        }
+        if let Some(pattern) = layer.pattern {
+            self.pattern = Some(pattern);
+            set("pattern");
+        }
+        if let Some(output_patterns) = layer.output_patterns {
+            self.output_patterns = output_patterns;
+            set("output_patterns");
+        }
+        if let Some(format) = layer.format {
+            self.format = format;
+            set("format");
+        }
+        if let Some(output_formats) = layer.output_formats {
+            self.output_formats = output_formats;
+            set("output_formats");
+        }
+        if let Some(json_fields) = layer.json_fields {
+            self.json_fields = json_fields;
+            set("json_fields");
+        }
+        if let Some(service) = layer.service {
+            self.service = service;
+            set("service");
+        }
+        if let Some(otlp) = layer.otlp {
+            self.otlp = otlp;
+            set("otlp");
+        }
+    }
+
+    /// Where the value of `field` came from.
+    ///
+    /// Only known for configs built by [PartialLogConfig::merge] or [LogConfig::apply],
+    /// and for values filled in with defaults by [crate::new].
+    pub fn get_source(&self, field: &str) -> Option<&ConfigSource> {
+        self.sources.get(field)
+    }
+
+    pub fn get_sources(&self) -> &ConfigSources {
+        &self.sources
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use log::LevelFilter;
+
+    #[test]
+    fn test_merge_layers() {
+        let base: PartialLogConfig = serde_json::from_str(
+            r#"{"level":"info","out_kind":["file"],"size":100,"roll_count":5}"#,
+        )
+        .unwrap();
+        let base = base.with_source(ConfigSource::File("base.json".into()));
+        let site: PartialLogConfig = serde_json::from_str(r#"{"level":"debug,app=warn"}"#).unwrap();
+        let site = site.with_source(ConfigSource::File("site.json".into()));
+        let env = PartialLogConfig {
+            size: Some(10),
+            ..Default::default()
+        }
+        .with_source(ConfigSource::Env("SIMPLE_LOG".to_string()));
+        let cli = PartialLogConfig {
+            out_kind: Some(vec![OutKind::Console]),
+            ..Default::default()
+        };
+
+        let config = PartialLogConfig::merge([base, site, env, cli]);
+        assert_eq!(
+            config.level,
+            (LevelFilter::Debug, vec![("app", LevelFilter::Warn).into()])
+        );
+        assert_eq!(config.size, 10);
+        assert_eq!(config.roll_count, 5);
+        assert_eq!(config.out_kind, vec![OutKind::Console]);
+        assert_eq!(config.path, None);
+
+        // the sources are not part of the config value
+        let json = serde_json::to_string(&config).unwrap();
+        let round_trip: LogConfig = serde_json::from_str(&json).unwrap();
+        assert!(round_trip.get_sources().is_empty());
+        assert_eq!(round_trip, config);
+
+        let sources: Vec<_> = config
+            .get_sources()
+            .iter()
+            .map(|(field, source)| format!("{}: {}", field, source))
+            .collect();
+        assert_eq!(
+            sources,
+            vec![
+                "archive_pattern: default",
+                "compression: default",
+                "current_link: default",
+                "directory: default",
+                "external_rotation: default",
+                "files: default",
+                "format: default",
+                "json_fields: default",
+                "level: file site.json",
+                "max_age: default",
+                "max_total_size: default",
+                "otlp: default",
+                "out_kind: code",
+                "output_formats: default",
+                "output_levels: default",
+                "output_patterns: default",
+                "path: default",
+                "pattern: default",
+                "roll_count: file base.json",
+                "roll_on_startup: default",
+                "rotation: default",
+                "routes: default",
+                "service: default",
+                "size: env SIMPLE_LOG_*",
+                "time_format: default",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_partial_defaults_to_none() {
+        let layer: PartialLogConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(layer, PartialLogConfig::default());
+    }
+}
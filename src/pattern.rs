@@ -0,0 +1,244 @@
+//! Check log4rs patterns, e.g. `{d(%H:%M:%S)} [{l}] {T} {P} {f}:{L} {m}{n}`.
+//!
+//! log4rs renders a bad pattern token as `{ERROR: ..}` into every line, so patterns
+//! are checked when the config is built instead, and the error points to the bad token.
+
+use crate::{SimpleLogError, SimpleResult};
+use chrono::format::{Item, StrftimeItems};
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+/// Formatters without arguments.
+const NO_ARGS: [&str; 21] = [
+    "l",
+    "level",
+    "m",
+    "message",
+    "M",
+    "module",
+    "n",
+    "f",
+    "file",
+    "L",
+    "line",
+    "T",
+    "thread",
+    "I",
+    "thread_id",
+    "P",
+    "pid",
+    "i",
+    "tid",
+    "t",
+    "target",
+];
+
+/// A byte offset in the pattern and what is wrong there.
+type Check<T> = Result<T, (usize, String)>;
+
+/// Fails with [SimpleLogError::ParsePattern] if log4rs can't render `pattern`.
+pub(crate) fn check_pattern(pattern: &str) -> SimpleResult<()> {
+    let mut checker = PatternChecker {
+        pattern,
+        it: pattern.char_indices().peekable(),
+    };
+    checker
+        .pieces(None)
+        .map_err(|(offset, message)| SimpleLogError::ParsePattern {
+            input: pattern.to_string(),
+            offset,
+            message,
+        })
+}
+
+/// Follows the grammar of the log4rs pattern parser.
+struct PatternChecker<'a> {
+    pattern: &'a str,
+    it: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> PatternChecker<'a> {
+    fn offset(&mut self) -> usize {
+        self.it.peek().map_or(self.pattern.len(), |&(pos, _)| pos)
+    }
+
+    fn consume(&mut self, ch: char) -> bool {
+        self.it.next_if(|&(_, c)| c == ch).is_some()
+    }
+
+    /// Consume the character escaped by a `\`.
+    fn escaped(&mut self) -> bool {
+        self.it
+            .next_if(|&(_, c)| matches!(c, '{' | '}' | '(' | ')' | '\\'))
+            .is_some()
+    }
+
+    /// Text and formatters, up to the `)` of the argument opened at `open`.
+    fn pieces(&mut self, open: Option<usize>) -> Check<()> {
+        while let Some((pos, ch)) = self.it.next() {
+            match ch {
+                '{' if !self.consume('{') => self.formatter()?,
+                '}' if !self.consume('}') => return Err((pos, "unmatched '}'".to_string())),
+                '(' if !self.consume('(') => return Err((pos, "unexpected '('".to_string())),
+                ')' if open.is_some() => return Ok(()),
+                ')' if !self.consume(')') => return Err((pos, "unexpected ')'".to_string())),
+                '\\' if !self.escaped() => return Err((pos, "unexpected '\\'".to_string())),
+                _ => {}
+            }
+        }
+        match open {
+            Some(open) => Err((open, "unclosed '('".to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// `name(arg)..:format}`, after the `{`.
+    fn formatter(&mut self) -> Check<()> {
+        let start = self.offset();
+        let name = self.name();
+        let mut args = vec![];
+        while let Some((open, _)) = self.it.next_if(|&(_, c)| c == '(') {
+            self.pieces(Some(open))?;
+            // the argument without its parentheses
+            args.push(&self.pattern[open + 1..self.offset() - 1]);
+        }
+        self.parameters();
+        if !self.consume('}') {
+            return Err((self.offset(), "expected '}'".to_string()));
+        }
+        check_args(name, &args).map_err(|message| (start, message))
+    }
+
+    fn name(&mut self) -> &'a str {
+        let start = self.offset();
+        if self.it.next_if(|&(_, c)| c.is_alphabetic()).is_some() {
+            while self.it.next_if(|&(_, c)| c.is_alphanumeric()).is_some() {}
+        }
+        &self.pattern[start..self.offset()]
+    }
+
+    /// `:[fill]<|>[-][min][.max]`, log4rs accepts anything here.
+    fn parameters(&mut self) {
+        if !self.consume(':') {
+            return;
+        }
+        let mut lookahead = self.it.clone();
+        lookahead.next();
+        if matches!(lookahead.peek(), Some((_, '<' | '>'))) {
+            self.it.next();
+        }
+        let _ = self.consume('<') || self.consume('>');
+        self.consume('-');
+        while self.it.next_if(|&(_, c)| c.is_ascii_digit()).is_some() {}
+        if self.consume('.') {
+            while self.it.next_if(|&(_, c)| c.is_ascii_digit()).is_some() {}
+        }
+    }
+}
+
+fn check_args(name: &str, args: &[&str]) -> Result<(), String> {
+    let expect = |count: &str| {
+        Err(format!(
+            "formatter `{}` expects {} argument(s), got {}",
+            name,
+            count,
+            args.len()
+        ))
+    };
+    match name {
+        "d" | "date" => {
+            if args.len() > 2 {
+                return expect("at most 2");
+            }
+            if let Some(format) = args.first() {
+                if StrftimeItems::new(format).any(|item| item == Item::Error) {
+                    return Err(format!("invalid date format `{}`", format));
+                }
+            }
+            match args.get(1) {
+                None | Some(&"utc") | Some(&"local") => Ok(()),
+                Some(timezone) => Err(format!(
+                    "invalid timezone `{}`, expect `utc` or `local`",
+                    timezone
+                )),
+            }
+        }
+        "h" | "highlight" | "D" | "debug" | "R" | "release" if args.len() != 1 => expect("1"),
+        "h" | "highlight" | "D" | "debug" | "R" | "release" => Ok(()),
+        // `{(..):>15}` aligns a group
+        "" if args.len() == 1 => Ok(()),
+        "" => Err("expected a formatter name".to_string()),
+        "X" | "mdc" if args.is_empty() || args.len() > 2 => expect("1 or 2"),
+        "X" | "mdc" => Ok(()),
+        "K" | "key_value" => Err(format!("formatter `{}` is not supported", name)),
+        name if NO_ARGS.contains(&name) && !args.is_empty() => expect("0"),
+        name if NO_ARGS.contains(&name) => Ok(()),
+        name => Err(format!("unknown formatter `{}`", name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(pattern: &str) -> (usize, String) {
+        match check_pattern(pattern) {
+            Err(SimpleLogError::ParsePattern {
+                offset, message, ..
+            }) => (offset, message),
+            other => panic!("{}: {:?}", pattern, other),
+        }
+    }
+
+    #[test]
+    fn test_check_pattern() {
+        for pattern in [
+            "",
+            "{d(%Y-%m-%d %H:%M:%S.%f)} [{h({l:5})}] <{M}:{L}>:{m}{n}",
+            "{d(%H:%M:%S)(utc)} [{T}] {P} {f}:{L} {t:>7.7} {m}{n}",
+            "{({l} {m}):>15} {X(user)(-)}",
+            "{{literal}} \\(x\\) ((x))",
+        ] {
+            assert!(check_pattern(pattern).is_ok(), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_check_pattern_error() {
+        assert_eq!(
+            error("{d} {x} {m}"),
+            (5, "unknown formatter `x`".to_string())
+        );
+        assert_eq!(error("{m} {l"), (6, "expected '}'".to_string()));
+        assert_eq!(error("{m}}"), (3, "unmatched '}'".to_string()));
+        assert_eq!(
+            error("{m} {}"),
+            (5, "expected a formatter name".to_string())
+        );
+        assert_eq!(error("{h({l} {m}"), (2, "unclosed '('".to_string()));
+        assert_eq!(
+            error("{l(x)}"),
+            (1, "formatter `l` expects 0 argument(s), got 1".to_string())
+        );
+        assert_eq!(
+            error("{d(%Y)(mars)}"),
+            (
+                1,
+                "invalid timezone `mars`, expect `utc` or `local`".to_string()
+            )
+        );
+        assert_eq!(
+            error("{d(%Q)}"),
+            (1, "invalid date format `%Q`".to_string())
+        );
+    }
+
+    #[test]
+    fn test_pattern_error_display() {
+        let err = check_pattern("{d} {x}{n}").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to parse pattern at offset 5: unknown formatter `x`\n{d} {x}{n}\n     ^"
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! Reopen the log file after an external tool, e.g. logrotate, moved it away.
+//!
+//! With `external_rotation` the file appender never rolls by itself. Before a write, at
+//! most once per [REOPEN_CHECK_INTERVAL], the path is compared with the file that is open,
+//! and the file is reopened when the path was moved, deleted or replaced.
+
+use log4rs::append::rolling_file::policy::compound::roll::Roll;
+use log4rs::append::rolling_file::policy::compound::trigger::Trigger;
+use log4rs::append::rolling_file::LogFile;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// How often the path is checked against the open file.
+pub(crate) const REOPEN_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Identifies a file independently of its path: device and inode on unix.
+#[cfg(unix)]
+type FileId = (u64, u64);
+
+/// Identifies a file independently of its path: the creation time where there are no inodes.
+#[cfg(not(unix))]
+type FileId = std::time::SystemTime;
+
+#[cfg(unix)]
+fn file_id(path: &Path) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(path: &Path) -> Option<FileId> {
+    fs::metadata(path).and_then(|m| m.created()).ok()
+}
+
+#[derive(Debug)]
+struct ReopenState {
+    checked: Instant,
+    /// The open file, `None` until the reopened file is seen at the path.
+    opened: Option<FileId>,
+}
+
+/// Triggers when the path no longer points at the open file, so log4rs reopens it.
+#[derive(Debug)]
+pub(crate) struct ReopenTrigger {
+    path: PathBuf,
+    interval: Duration,
+    state: Mutex<ReopenState>,
+}
+
+impl ReopenTrigger {
+    pub(crate) fn new(path: PathBuf, interval: Duration) -> ReopenTrigger {
+        let opened = file_id(&path);
+        ReopenTrigger {
+            path,
+            interval,
+            state: Mutex::new(ReopenState {
+                checked: Instant::now(),
+                opened,
+            }),
+        }
+    }
+
+    fn should_reopen(&self, now: Instant) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if now.saturating_duration_since(state.checked) < self.interval {
+            return false;
+        }
+        state.checked = now;
+        let current = file_id(&self.path);
+        match (state.opened, current) {
+            (Some(opened), Some(current)) if opened == current => false,
+            (None, Some(current)) => {
+                state.opened = Some(current);
+                false
+            }
+            _ => {
+                state.opened = current;
+                true
+            }
+        }
+    }
+}
+
+impl Trigger for ReopenTrigger {
+    fn trigger(&self, _file: &LogFile) -> anyhow::Result<bool> {
+        Ok(self.should_reopen(Instant::now()))
+    }
+
+    fn is_pre_process(&self) -> bool {
+        // reopen before writing, so the record goes to the new file
+        true
+    }
+}
+
+/// Leaves the file alone, the external tool already moved it.
+#[derive(Debug)]
+pub(crate) struct KeepRoller;
+
+impl Roll for KeepRoller {
+    fn roll(&self, _file: &Path) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_reopen() {
+        let dir = std::env::temp_dir().join("simple_log_test_reopen");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.log");
+        fs::write(&path, "first\n").unwrap();
+
+        let trigger = ReopenTrigger::new(path.clone(), REOPEN_CHECK_INTERVAL);
+        let start = Instant::now();
+        assert!(!trigger.should_reopen(start + REOPEN_CHECK_INTERVAL));
+
+        // logrotate: move and create
+        fs::rename(&path, dir.join("app.log.1")).unwrap();
+        fs::write(&path, "").unwrap();
+        // not checked again within the interval
+        assert!(!trigger.should_reopen(start + REOPEN_CHECK_INTERVAL));
+        assert!(trigger.should_reopen(start + REOPEN_CHECK_INTERVAL * 2));
+        assert!(!trigger.should_reopen(start + REOPEN_CHECK_INTERVAL * 3));
+
+        // moved without create: reopen, then track the created file
+        fs::rename(&path, dir.join("app.log.2")).unwrap();
+        assert!(trigger.should_reopen(start + REOPEN_CHECK_INTERVAL * 4));
+        fs::write(&path, "").unwrap();
+        assert!(!trigger.should_reopen(start + REOPEN_CHECK_INTERVAL * 5));
+        assert!(!trigger.should_reopen(start + REOPEN_CHECK_INTERVAL * 6));
+    }
+}
@@ -0,0 +1,199 @@
+//! Delete old archives by count, age and total size.
+
+use crate::archive::Archive;
+use crate::LogConfig;
+use serde::{Deserialize, Deserializer, Serializer};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+/// Which archives to keep, applied after each roll and when the file appender is built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Retention {
+    /// `roll_count`.
+    pub(crate) max_count: usize,
+    max_age: Option<Duration>,
+    /// In bytes.
+    max_total_size: Option<u64>,
+}
+
+impl Retention {
+    pub(crate) fn new(
+        max_count: usize,
+        max_age: Option<Duration>,
+        max_total_size: Option<u64>,
+    ) -> Retention {
+        Retention {
+            max_count,
+            max_age,
+            max_total_size,
+        }
+    }
+
+    pub(crate) fn from_config(log: &LogConfig) -> Retention {
+        Retention::new(
+            log.roll_count as usize,
+            log.max_age,
+            log.max_total_size.map(|size| size * 1024 * 1024),
+        )
+    }
+
+    /// The archives to delete from `archives`, sorted newest first.
+    ///
+    /// An archive is deleted when it is past `max_count`, older than `max_age`,
+    /// or when it and the newer archives exceed `max_total_size`.
+    fn expired<'a>(&self, archives: &'a [Archive], now: SystemTime) -> Vec<&'a Path> {
+        let mut total = 0u64;
+        archives
+            .iter()
+            .enumerate()
+            .filter(|(i, archive)| {
+                total = total.saturating_add(archive.len);
+                let too_old = self.max_age.is_some_and(|max_age| {
+                    now.duration_since(archive.modified).unwrap_or_default() > max_age
+                });
+                let too_big = self.max_total_size.is_some_and(|max| total > max);
+                *i >= self.max_count || too_old || too_big
+            })
+            .map(|(_, archive)| archive.path.as_path())
+            .collect()
+    }
+
+    /// Delete the expired `archives`, sorted newest first.
+    pub(crate) fn cleanup(&self, archives: &[Archive]) -> io::Result<()> {
+        for archive in self.expired(archives, SystemTime::now()) {
+            match fs::remove_file(archive) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Serialize `max_age` as a human readable duration, e.g. `"14days"`.
+pub(crate) fn serialize_max_age<S>(
+    max_age: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match max_age {
+        Some(max_age) => serializer.collect_str(&humantime::format_duration(*max_age)),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Deserialize `max_age` from a human readable duration, e.g. `"14d"` or `"12h"`.
+pub(crate) fn deserialize_max_age<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let max_age: Option<String> = Option::deserialize(deserializer)?;
+    max_age
+        .map(|s| humantime::parse_duration(s.trim()).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::ArchiveNames;
+    use crate::compression::Compression;
+    use std::path::PathBuf;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    fn archives(now: SystemTime) -> Vec<Archive> {
+        (0..5)
+            .map(|i| Archive {
+                path: PathBuf::from(format!("app.log.{}.gz", i)),
+                modified: now - DAY * i,
+                len: 100,
+            })
+            .collect()
+    }
+
+    fn names(expired: Vec<&Path>) -> Vec<String> {
+        expired.iter().map(|p| p.display().to_string()).collect()
+    }
+
+    #[test]
+    fn test_expired() {
+        let now = SystemTime::now();
+        let archives = archives(now);
+
+        let retention = Retention::new(10, None, None);
+        assert!(retention.expired(&archives, now).is_empty());
+
+        let retention = Retention::new(3, None, None);
+        assert_eq!(
+            names(retention.expired(&archives, now)),
+            vec!["app.log.3.gz", "app.log.4.gz"]
+        );
+
+        let retention = Retention::new(10, Some(DAY * 2), None);
+        assert_eq!(
+            names(retention.expired(&archives, now + Duration::from_secs(1))),
+            vec!["app.log.2.gz", "app.log.3.gz", "app.log.4.gz"]
+        );
+
+        let retention = Retention::new(10, None, Some(250));
+        assert_eq!(
+            names(retention.expired(&archives, now)),
+            vec!["app.log.2.gz", "app.log.3.gz", "app.log.4.gz"]
+        );
+    }
+
+    #[test]
+    fn test_cleanup() {
+        let dir = std::env::temp_dir().join("simple_log_test_cleanup");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.log");
+        let now = SystemTime::now();
+        for (name, age) in [("0", 0), ("1", 1), ("2", 20)] {
+            let file = fs::File::create(dir.join(format!("app.log.{}.gz", name))).unwrap();
+            file.set_modified(now - DAY * age).unwrap();
+        }
+        fs::write(dir.join("app.log"), "").unwrap();
+        fs::write(dir.join("other.log.0.gz"), "").unwrap();
+
+        let gzip = Compression::Gzip { level: 6 };
+        let archives = ArchiveNames::new(&path, None, None, gzip).unwrap();
+        Retention::new(10, Some(DAY * 14), None)
+            .cleanup(&archives.list(&path).unwrap())
+            .unwrap();
+        let mut names: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec!["app.log", "app.log.0.gz", "app.log.1.gz", "other.log.0.gz"]
+        );
+
+        let not_exist = dir.join("not_exist").join("app.log");
+        assert!(archives.list(&not_exist).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_max_age_serde() {
+        #[derive(serde::Serialize, Deserialize)]
+        struct Config {
+            #[serde(
+                serialize_with = "serialize_max_age",
+                deserialize_with = "deserialize_max_age"
+            )]
+            max_age: Option<Duration>,
+        }
+        let config: Config = serde_json::from_str(r#"{"max_age":"14d"}"#).unwrap();
+        assert_eq!(config.max_age, Some(DAY * 14));
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(json, r#"{"max_age":"14days"}"#);
+        assert!(serde_json::from_str::<Config>(r#"{"max_age":"14 weeks ago"}"#).is_err());
+    }
+}
@@ -0,0 +1,875 @@
+//! Time based rotation of the log file.
+//!
+//! [Rotation] is configured with a string:
+//!
+//! ```bash
+//! rotation = "size"        # roll when the file reaches `size` MB, the default
+//! rotation = "daily"       # roll at midnight
+//! rotation = "hourly"      # roll at every full hour
+//! rotation = "15m"         # roll every 15 minutes, counted from midnight
+//! rotation = "size,daily"  # roll at midnight, or earlier when the file reaches `size` MB
+//! ```
+//!
+//! Time based archives carry the period they were written in,
+//! e.g. `app.log.2024-03-01.gz` or `app.log.2024-03-01-13.gz`.
+
+use crate::archive::{file_name, ArchiveNames};
+use crate::compression::Compression;
+use crate::retention::Retention;
+use crate::SimpleLogError;
+use chrono::{DateTime, Local, NaiveDateTime, NaiveTime, Timelike};
+use log4rs::append::rolling_file::policy::compound::roll::Roll;
+use log4rs::append::rolling_file::policy::compound::trigger::Trigger;
+use log4rs::append::rolling_file::LogFile;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::SystemTime;
+
+/// Rolled files that could not be archived, see [archive_errors].
+static ARCHIVE_ERRORS: AtomicUsize = AtomicUsize::new(0);
+
+const ROTATION_EXPECT: &str =
+    "expect rotation 'size', 'daily', 'hourly', '<N>m' or 'size,<period>' e.g. 'size,daily'";
+
+/// How often time based rotation rolls the log file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotationPeriod {
+    /// At midnight.
+    Daily,
+    /// At every full hour.
+    Hourly,
+    /// Every N minutes, counted from midnight.
+    Minutes(u32),
+}
+
+impl RotationPeriod {
+    /// The start of the period containing `time`.
+    pub(crate) fn start(&self, time: NaiveDateTime) -> NaiveDateTime {
+        let minutes = match self {
+            RotationPeriod::Daily => 0,
+            RotationPeriod::Hourly => time.hour() * 60,
+            RotationPeriod::Minutes(n) => {
+                let n = (*n).max(1);
+                (time.hour() * 60 + time.minute()) / n * n
+            }
+        };
+        let start =
+            NaiveTime::from_hms_opt(minutes / 60, minutes % 60, 0).unwrap_or(NaiveTime::MIN);
+        time.date().and_time(start)
+    }
+
+    /// The archive name pattern of the period, e.g. `%Y-%m-%d` for [RotationPeriod::Daily].
+    pub(crate) fn label_format(&self) -> &'static str {
+        match self {
+            RotationPeriod::Daily => "%Y-%m-%d",
+            RotationPeriod::Hourly => "%Y-%m-%d-%H",
+            RotationPeriod::Minutes(_) => "%Y-%m-%d-%H-%M",
+        }
+    }
+}
+
+impl fmt::Display for RotationPeriod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RotationPeriod::Daily => f.write_str("daily"),
+            RotationPeriod::Hourly => f.write_str("hourly"),
+            RotationPeriod::Minutes(n) => write!(f, "{}m", n),
+        }
+    }
+}
+
+/// When the log file is rolled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rotation {
+    /// When the file reaches `size` MB.
+    #[default]
+    Size,
+    /// At the end of every period.
+    Time(RotationPeriod),
+    /// At the end of every period, or earlier when the file reaches `size` MB.
+    SizeOrTime(RotationPeriod),
+}
+
+impl Rotation {
+    pub fn period(&self) -> Option<RotationPeriod> {
+        match self {
+            Rotation::Size => None,
+            Rotation::Time(period) | Rotation::SizeOrTime(period) => Some(*period),
+        }
+    }
+
+    pub fn by_size(&self) -> bool {
+        matches!(self, Rotation::Size | Rotation::SizeOrTime(_))
+    }
+}
+
+impl fmt::Display for Rotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rotation::Size => f.write_str("size"),
+            Rotation::Time(period) => write!(f, "{}", period),
+            Rotation::SizeOrTime(period) => write!(f, "size,{}", period),
+        }
+    }
+}
+
+impl FromStr for Rotation {
+    type Err = SimpleLogError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || {
+            SimpleLogError::InvalidRotation(format!("Invalid rotation '{}',{}", s, ROTATION_EXPECT))
+        };
+        let mut size = false;
+        let mut period = None;
+        for token in s.split(',').map(|t| t.trim().to_ascii_lowercase()) {
+            let parsed = match token.as_str() {
+                "size" if !size => {
+                    size = true;
+                    continue;
+                }
+                "daily" => RotationPeriod::Daily,
+                "hourly" => RotationPeriod::Hourly,
+                minutes => match minutes.strip_suffix('m').map(str::parse::<u32>) {
+                    Some(Ok(n)) if n > 0 => RotationPeriod::Minutes(n),
+                    _ => return Err(err()),
+                },
+            };
+            if period.replace(parsed).is_some() {
+                return Err(err());
+            }
+        }
+        match (size, period) {
+            (true, None) => Ok(Rotation::Size),
+            (false, Some(period)) => Ok(Rotation::Time(period)),
+            (true, Some(period)) => Ok(Rotation::SizeOrTime(period)),
+            (false, None) => Err(err()),
+        }
+    }
+}
+
+impl Serialize for Rotation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rotation {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// The source of the current time, replaced in tests.
+pub(crate) trait Clock: fmt::Debug + Send + Sync {
+    fn now(&self) -> NaiveDateTime;
+}
+
+#[derive(Debug)]
+pub(crate) struct LocalClock;
+
+impl Clock for LocalClock {
+    fn now(&self) -> NaiveDateTime {
+        Local::now().naive_local()
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Rolls the file when the period changes, and by size for [Rotation::SizeOrTime].
+///
+/// The start of the period being archived is handed to [ArchiveRoller].
+#[derive(Debug)]
+pub(crate) struct RotationTrigger {
+    rotation: Rotation,
+    size_limit: u64,
+    clock: Arc<dyn Clock>,
+    period_start: Mutex<NaiveDateTime>,
+    archive_period: Arc<Mutex<Option<NaiveDateTime>>>,
+}
+
+impl RotationTrigger {
+    /// `last_write` is the modified time of an existing log file, so a file left over
+    /// from an earlier period is rolled on the first write.
+    pub(crate) fn new(
+        rotation: Rotation,
+        size_limit: u64,
+        clock: Arc<dyn Clock>,
+        last_write: Option<NaiveDateTime>,
+        archive_period: Arc<Mutex<Option<NaiveDateTime>>>,
+    ) -> RotationTrigger {
+        let last_write = last_write.unwrap_or_else(|| clock.now());
+        let period_start = match rotation.period() {
+            Some(period) => period.start(last_write),
+            None => last_write,
+        };
+        RotationTrigger {
+            rotation,
+            size_limit,
+            clock,
+            period_start: Mutex::new(period_start),
+            archive_period,
+        }
+    }
+
+    fn should_roll(&self, len: u64) -> bool {
+        let mut period_start = lock(&self.period_start);
+        let period = self.rotation.period();
+        if let Some(period) = period {
+            let now_start = period.start(self.clock.now());
+            if now_start != *period_start {
+                *lock(&self.archive_period) = Some(*period_start);
+                *period_start = now_start;
+                return true;
+            }
+        }
+        if self.rotation.by_size() && len > self.size_limit {
+            *lock(&self.archive_period) = period.map(|_| *period_start);
+            return true;
+        }
+        false
+    }
+}
+
+impl Trigger for RotationTrigger {
+    fn trigger(&self, file: &LogFile) -> anyhow::Result<bool> {
+        Ok(self.should_roll(file.len_estimate()))
+    }
+
+    fn is_pre_process(&self) -> bool {
+        // roll before writing, so the first record of a period goes to the new file
+        self.rotation.period().is_some()
+    }
+}
+
+/// The modified time of `path` in local time, if it exists.
+pub(crate) fn last_write(path: &Path) -> Option<NaiveDateTime> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    Some(DateTime::<Local>::from(modified).naive_local())
+}
+
+/// The number of rolled log files that failed to be archived or cleaned up since start.
+///
+/// Archiving runs in the background, each failure is also logged at error level with the
+/// `simple_log` target.
+pub fn archive_errors() -> usize {
+    ARCHIVE_ERRORS.load(Ordering::Relaxed)
+}
+
+/// A rolled file to archive, with the settings of the config that rolled it.
+#[derive(Debug)]
+struct ArchiveJob {
+    rolled: PathBuf,
+    time: NaiveDateTime,
+    names: ArchiveNames,
+    compression: Compression,
+    retention: Retention,
+}
+
+/// Archives the rolled files of one log file on a background thread, one at a time.
+///
+/// It is kept across config updates, so archives of a file are never named by two threads
+/// at once. The thread stops once the last clone is dropped.
+#[derive(Debug, Clone)]
+pub(crate) struct Archiver {
+    jobs: Arc<Mutex<Sender<ArchiveJob>>>,
+}
+
+impl Archiver {
+    pub(crate) fn new(path: PathBuf) -> Archiver {
+        let (jobs, pending) = mpsc::channel::<ArchiveJob>();
+        // compress off the logging thread
+        thread::spawn(move || {
+            for job in pending {
+                let result = archive(
+                    &path,
+                    &job.rolled,
+                    job.time,
+                    &job.names,
+                    job.compression,
+                    &job.retention,
+                );
+                if let Err(e) = result {
+                    ARCHIVE_ERRORS.fetch_add(1, Ordering::Relaxed);
+                    log::error!(
+                        target: "simple_log",
+                        "Failed to archive {}: {}",
+                        job.rolled.display(),
+                        e
+                    );
+                }
+            }
+        });
+        Archiver {
+            jobs: Arc::new(Mutex::new(jobs)),
+        }
+    }
+
+    /// Whether `other` is a clone of it, with the same archiving thread.
+    #[cfg(test)]
+    pub(crate) fn is_clone_of(&self, other: &Archiver) -> bool {
+        Arc::ptr_eq(&self.jobs, &other.jobs)
+    }
+}
+
+/// Rolls the log file aside and hands it to its [Archiver], which then applies the [Retention].
+#[derive(Debug)]
+pub(crate) struct ArchiveRoller {
+    names: ArchiveNames,
+    compression: Compression,
+    retention: Retention,
+    clock: Arc<dyn Clock>,
+    archive_period: Arc<Mutex<Option<NaiveDateTime>>>,
+    archiver: Archiver,
+}
+
+impl ArchiveRoller {
+    pub(crate) fn new(
+        archiver: Archiver,
+        names: ArchiveNames,
+        compression: Compression,
+        retention: Retention,
+        clock: Arc<dyn Clock>,
+        archive_period: Arc<Mutex<Option<NaiveDateTime>>>,
+    ) -> ArchiveRoller {
+        ArchiveRoller {
+            names,
+            compression,
+            retention,
+            clock,
+            archive_period,
+            archiver,
+        }
+    }
+}
+
+impl ArchiveRoller {
+    /// Move `file` aside and archive it as written at `time`.
+    fn roll_at(&self, file: &Path, time: NaiveDateTime) -> io::Result<()> {
+        let rolled = rolling_name(file);
+        fs::rename(file, &rolled)?;
+        self.archive(rolled, time)
+    }
+
+    fn archive(&self, rolled: PathBuf, time: NaiveDateTime) -> io::Result<()> {
+        let job = ArchiveJob {
+            rolled,
+            time,
+            names: self.names.clone(),
+            compression: self.compression,
+            retention: self.retention,
+        };
+        lock(&self.archiver.jobs)
+            .send(job)
+            .map_err(|e| io::Error::new(io::ErrorKind::BrokenPipe, e.to_string()))
+    }
+
+    /// Archive the files an earlier run rolled aside but never archived, e.g. after a crash.
+    pub(crate) fn archive_leftovers(
+        &self,
+        file: &Path,
+        period: Option<RotationPeriod>,
+    ) -> io::Result<()> {
+        let dir = match file.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let prefix = format!("{}.rolling", file_name(file));
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        let mut leftovers = vec![];
+        for entry in entries {
+            let path = entry?.path();
+            let name = file_name(&path);
+            let rolled = match name.strip_prefix(&prefix) {
+                Some("") => true,
+                Some(suffix) => suffix
+                    .strip_prefix('.')
+                    .is_some_and(|n| !n.is_empty() && n.chars().all(|ch| ch.is_ascii_digit())),
+                None => false,
+            };
+            if rolled {
+                leftovers.push(path);
+            }
+        }
+        // oldest first, so they are named in the order they were written
+        leftovers.sort_by_key(|path| last_write(path));
+        for rolled in leftovers {
+            let time = last_write(&rolled).unwrap_or_else(|| self.clock.now());
+            let time = period.map_or(time, |period| period.start(time));
+            self.archive(rolled, time)?;
+        }
+        Ok(())
+    }
+
+    /// Archive a non-empty `file` left by an earlier run, named by its last write.
+    pub(crate) fn roll_existing(
+        &self,
+        file: &Path,
+        period: Option<RotationPeriod>,
+    ) -> io::Result<()> {
+        match fs::metadata(file) {
+            Ok(metadata) if metadata.len() > 0 => {}
+            _ => return Ok(()),
+        }
+        let time = last_write(file).unwrap_or_else(|| self.clock.now());
+        let time = period.map_or(time, |period| period.start(time));
+        self.roll_at(file, time)
+    }
+}
+
+impl Roll for ArchiveRoller {
+    fn roll(&self, file: &Path) -> anyhow::Result<()> {
+        // size rotation names the archive by the time of the roll
+        let time = lock(&self.archive_period).unwrap_or_else(|| self.clock.now());
+        self.roll_at(file, time)?;
+        Ok(())
+    }
+}
+
+/// Point the symlink `link` at `file`, replacing an older link.
+///
+/// `link` is relative to the directory of `file`, unless absolute.
+pub(crate) fn link_current(file: &Path, link: &Path) -> io::Result<()> {
+    let dir = match file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let link = dir.join(link);
+    // a relative target keeps working when the directory is moved or mounted elsewhere
+    let target = if link.parent() == Some(dir) {
+        PathBuf::from(file_name(file))
+    } else {
+        std::path::absolute(file)?
+    };
+    match fs::symlink_metadata(&link) {
+        Ok(metadata) if !metadata.file_type().is_symlink() => {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} exists and is not a symlink", link.display()),
+            ));
+        }
+        Ok(_) if fs::read_link(&link)? == target => return Ok(()),
+        _ => {}
+    }
+    if let Some(parent) = link.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // create the new link aside and rename it over the old one, so readers always find a link
+    let tmp = rolling_name(&link);
+    symlink(&target, &tmp)?;
+    fs::rename(&tmp, &link).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(target, link)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn symlink(_target: &Path, _link: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "symlinks are not supported on this platform",
+    ))
+}
+
+/// A unique name next to `file` to hold it until it is archived.
+fn rolling_name(file: &Path) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let name = file_name(file);
+    (nanos..)
+        .map(|n| file.with_file_name(format!("{}.rolling.{}", name, n)))
+        .find(|p| !p.exists())
+        .unwrap_or_else(|| file.with_file_name(format!("{}.rolling", name)))
+}
+
+fn archive(
+    path: &Path,
+    rolled: &Path,
+    time: NaiveDateTime,
+    names: &ArchiveNames,
+    compression: Compression,
+    retention: &Retention,
+) -> io::Result<()> {
+    match names.next(path, time, retention.max_count)? {
+        Some(dst) => compression.compress(rolled, &dst)?,
+        None => fs::remove_file(rolled)?,
+    }
+    retention.cleanup(&names.list(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[derive(Debug)]
+    struct MockClock(Mutex<NaiveDateTime>);
+
+    impl MockClock {
+        fn set(&self, time: NaiveDateTime) {
+            *self.0.lock().unwrap() = time;
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> NaiveDateTime {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    fn time(d: u32, h: u32, m: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 3, d)
+            .unwrap()
+            .and_hms_opt(h, m, 7)
+            .unwrap()
+    }
+
+    fn trigger(rotation: Rotation, now: NaiveDateTime) -> (RotationTrigger, Arc<MockClock>) {
+        let clock = Arc::new(MockClock(Mutex::new(now)));
+        let period = Arc::new(Mutex::new(None));
+        let trigger = RotationTrigger::new(rotation, 100, clock.clone(), None, period);
+        (trigger, clock)
+    }
+
+    fn archive_period(trigger: &RotationTrigger) -> Option<NaiveDateTime> {
+        *trigger.archive_period.lock().unwrap()
+    }
+
+    fn start(d: u32, h: u32) -> Option<NaiveDateTime> {
+        Some(time(d, h, 0).with_second(0).unwrap())
+    }
+
+    #[test]
+    fn test_rotation_serde() {
+        for (input, rotation) in [
+            ("size", Rotation::Size),
+            ("daily", Rotation::Time(RotationPeriod::Daily)),
+            ("hourly", Rotation::Time(RotationPeriod::Hourly)),
+            ("15m", Rotation::Time(RotationPeriod::Minutes(15))),
+            ("size,daily", Rotation::SizeOrTime(RotationPeriod::Daily)),
+        ] {
+            assert_eq!(input.parse::<Rotation>().unwrap(), rotation);
+            assert_eq!(rotation.to_string(), input);
+        }
+        assert_eq!(
+            " Hourly , SIZE ".parse::<Rotation>().unwrap(),
+            Rotation::SizeOrTime(RotationPeriod::Hourly)
+        );
+        for input in ["", "weekly", "0m", "daily,hourly", "size,size"] {
+            assert!(input.parse::<Rotation>().is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_period_start() {
+        let t = time(1, 13, 47);
+        assert_eq!(
+            RotationPeriod::Daily.start(t),
+            time(1, 0, 0).with_second(0).unwrap()
+        );
+        assert_eq!(
+            RotationPeriod::Hourly.start(t),
+            time(1, 13, 0).with_second(0).unwrap()
+        );
+        assert_eq!(
+            RotationPeriod::Minutes(15).start(t),
+            time(1, 13, 45).with_second(0).unwrap()
+        );
+        assert_eq!(RotationPeriod::Minutes(15).label_format(), "%Y-%m-%d-%H-%M");
+    }
+
+    #[test]
+    fn test_daily_trigger() {
+        let (trigger, clock) = trigger(Rotation::Time(RotationPeriod::Daily), time(1, 23, 58));
+        assert!(trigger.is_pre_process());
+        assert!(!trigger.should_roll(1000));
+
+        clock.set(time(2, 0, 0));
+        assert!(trigger.should_roll(0));
+        assert_eq!(archive_period(&trigger), start(1, 0));
+        assert!(!trigger.should_roll(0));
+
+        clock.set(time(4, 12, 0));
+        assert!(trigger.should_roll(0));
+        assert_eq!(archive_period(&trigger), start(2, 0));
+    }
+
+    #[test]
+    fn test_size_or_hourly_trigger() {
+        let (trigger, clock) =
+            trigger(Rotation::SizeOrTime(RotationPeriod::Hourly), time(1, 9, 10));
+        assert!(!trigger.should_roll(100));
+        assert!(trigger.should_roll(101));
+        assert_eq!(archive_period(&trigger), start(1, 9));
+
+        clock.set(time(1, 10, 0));
+        assert!(trigger.should_roll(0));
+        assert_eq!(archive_period(&trigger), start(1, 9));
+    }
+
+    #[test]
+    fn test_size_trigger() {
+        let (trigger, _) = trigger(Rotation::Size, time(1, 9, 10));
+        assert!(!trigger.is_pre_process());
+        assert!(!trigger.should_roll(100));
+        assert!(trigger.should_roll(101));
+        assert_eq!(archive_period(&trigger), None);
+    }
+
+    #[test]
+    fn test_last_write_from_earlier_period() {
+        let clock = Arc::new(MockClock(Mutex::new(time(2, 8, 0))));
+        let period = Arc::new(Mutex::new(None));
+        let trigger = RotationTrigger::new(
+            Rotation::Time(RotationPeriod::Daily),
+            0,
+            clock,
+            Some(time(1, 22, 0)),
+            period.clone(),
+        );
+        assert!(trigger.should_roll(0));
+        assert_eq!(*period.lock().unwrap(), start(1, 0));
+    }
+
+    fn archive_names(path: &Path, names: &ArchiveNames) -> Vec<String> {
+        let mut names: Vec<_> = names
+            .list(path)
+            .unwrap()
+            .into_iter()
+            .map(|archive| file_name(&archive.path))
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Roll `path` 3 times in the same period, keeping 2 archives.
+    fn roll_3(
+        name: &str,
+        pattern: Option<&str>,
+        period: Option<RotationPeriod>,
+        compression: Compression,
+    ) -> (PathBuf, Vec<String>) {
+        let dir = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.log");
+        let names = ArchiveNames::new(&path, pattern, period, compression).unwrap();
+        let retention = Retention::new(2, None, None);
+
+        for i in 0..3 {
+            let rolled = rolling_name(&path);
+            fs::write(&rolled, format!("line {}", i)).unwrap();
+            archive(
+                &path,
+                &rolled,
+                time(1, 0, 0),
+                &names,
+                compression,
+                &retention,
+            )
+            .unwrap();
+            assert!(!rolled.exists());
+        }
+        let archives = archive_names(&path, &names);
+        (path, archives)
+    }
+
+    #[test]
+    fn test_archive_by_period() {
+        let (_, archives) = roll_3(
+            "simple_log_test_archive_period",
+            None,
+            Some(RotationPeriod::Daily),
+            Compression::None,
+        );
+        assert_eq!(
+            archives,
+            vec!["app.log.2024-03-01.1", "app.log.2024-03-01.2"]
+        );
+    }
+
+    #[test]
+    fn test_archive_by_index() {
+        let (path, archives) = roll_3(
+            "simple_log_test_archive_index",
+            None,
+            None,
+            Compression::default(),
+        );
+        assert_eq!(archives, vec!["app.log.0.gz", "app.log.1.gz"]);
+
+        let mut content = String::new();
+        let archive = fs::File::open(path.with_file_name("app.log.0.gz")).unwrap();
+        io::Read::read_to_string(&mut flate2::read::GzDecoder::new(archive), &mut content).unwrap();
+        assert_eq!(content, "line 2");
+    }
+
+    #[test]
+    fn test_archive_by_pattern() {
+        let (_, archives) = roll_3(
+            "simple_log_test_archive_pattern",
+            Some("app-%Y%m%d-%i.log.gz"),
+            None,
+            Compression::None,
+        );
+        assert_eq!(archives, vec!["app-20240301-1.log", "app-20240301-2.log"]);
+    }
+
+    #[test]
+    fn test_archive_errors() {
+        let dir = std::env::temp_dir().join("simple_log_test_archive_errors");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.log");
+        // a file is in the way of the archive directory
+        fs::write(dir.join("archive"), "").unwrap();
+        let names =
+            ArchiveNames::new(&path, Some("archive/app-%i.log"), None, Compression::None).unwrap();
+        let roller = ArchiveRoller::new(
+            Archiver::new(path.clone()),
+            names,
+            Compression::None,
+            Retention::new(10, None, None),
+            Arc::new(LocalClock),
+            Arc::new(Mutex::new(None)),
+        );
+
+        let errors = archive_errors();
+        fs::write(&path, "last run").unwrap();
+        roller.roll(&path).unwrap();
+        for _ in 0..100 {
+            if archive_errors() > errors {
+                break;
+            }
+            thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert!(archive_errors() > errors);
+    }
+
+    #[test]
+    fn test_roll_existing() {
+        let dir = std::env::temp_dir().join("simple_log_test_roll_existing");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.log");
+        let period = Some(RotationPeriod::Daily);
+        let names = ArchiveNames::new(&path, None, period, Compression::None).unwrap();
+        let clock = Arc::new(MockClock(Mutex::new(time(2, 8, 0))));
+        let roller = ArchiveRoller::new(
+            Archiver::new(path.clone()),
+            names.clone(),
+            Compression::None,
+            Retention::new(10, None, None),
+            clock,
+            Arc::new(Mutex::new(None)),
+        );
+
+        // nothing to roll
+        roller.roll_existing(&path, period).unwrap();
+        fs::write(&path, "").unwrap();
+        roller.roll_existing(&path, period).unwrap();
+        assert!(path.exists());
+
+        fs::write(&path, "last run").unwrap();
+        roller.roll_existing(&path, period).unwrap();
+        assert!(!path.exists());
+        for _ in 0..100 {
+            if !names.list(&path).unwrap().is_empty() {
+                break;
+            }
+            thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let archive = &names.list(&path).unwrap()[0];
+        let label = Local::now().format("%Y-%m-%d").to_string();
+        assert_eq!(file_name(&archive.path), format!("app.log.{}", label));
+        assert_eq!(fs::read_to_string(&archive.path).unwrap(), "last run");
+    }
+
+    #[test]
+    fn test_archive_leftovers() {
+        let dir = std::env::temp_dir().join("simple_log_test_archive_leftovers");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.log");
+        let names = ArchiveNames::new(&path, None, None, Compression::None).unwrap();
+        let roller = ArchiveRoller::new(
+            Archiver::new(path.clone()),
+            names.clone(),
+            Compression::None,
+            Retention::new(10, None, None),
+            Arc::new(LocalClock),
+            Arc::new(Mutex::new(None)),
+        );
+        for name in [
+            "app.log.rolling.1709269509000000000",
+            "app.log.rolling",
+            "app.log.rolling.tmp",
+            "other.log.rolling.1",
+        ] {
+            fs::write(dir.join(name), name).unwrap();
+        }
+
+        roller.archive_leftovers(&path, None).unwrap();
+        for _ in 0..100 {
+            if names.list(&path).unwrap().len() == 2 {
+                break;
+            }
+            thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(names.list(&path).unwrap().len(), 2);
+        assert!(!dir.join("app.log.rolling").exists());
+        assert!(dir.join("app.log.rolling.tmp").exists());
+        assert!(dir.join("other.log.rolling.1").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_link_current() {
+        let dir = std::env::temp_dir().join("simple_log_test_link_current");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.log");
+        fs::write(&path, "active").unwrap();
+
+        link_current(&path, Path::new("current.log")).unwrap();
+        let link = dir.join("current.log");
+        assert_eq!(fs::read_link(&link).unwrap(), PathBuf::from("app.log"));
+        assert_eq!(fs::read_to_string(&link).unwrap(), "active");
+
+        // replaces the link of an earlier file, in another directory the target is absolute
+        let other = dir.join("other.log");
+        link_current(&other, Path::new("current.log")).unwrap();
+        assert_eq!(fs::read_link(&link).unwrap(), PathBuf::from("other.log"));
+        link_current(&path, Path::new("links/current.log")).unwrap();
+        assert_eq!(fs::read_link(dir.join("links/current.log")).unwrap(), path);
+
+        fs::write(dir.join("plain.log"), "").unwrap();
+        assert!(link_current(&path, Path::new("plain.log")).is_err());
+    }
+}
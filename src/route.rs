@@ -0,0 +1,93 @@
+//! Route the records of a target to chosen outputs.
+//!
+//! Outputs are named `console`, `file`, `otlp`, or by the `name` of a [crate::FileOutput].
+//! Levels route by the `output_levels` entry of each output.
+//!
+//! ```toml
+//! out_kind = ["console", "file"]
+//! path = "app.log"
+//!
+//! # warn and above also goes to error.log
+//! [[files]]
+//! name = "error"
+//! path = "error.log"
+//!
+//! # only app::audit goes to audit.log
+//! [[files]]
+//! name = "audit"
+//! path = "audit.log"
+//! routed_only = true
+//!
+//! # app::audit goes only to the audit file
+//! [[routes]]
+//! target = "app::audit"
+//! outputs = ["audit"]
+//!
+//! # hyper never reaches the console
+//! [[routes]]
+//! target = "hyper"
+//! outputs = ["file", "error"]
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+/// The output name of the `console` out kind.
+pub(crate) const OUTPUT_CONSOLE: &str = "console";
+/// The output name of the `file` out kind.
+pub(crate) const OUTPUT_FILE: &str = "file";
+/// The output name of the `otlp` out kind.
+pub(crate) const OUTPUT_OTLP: &str = "otlp";
+
+/// Send the records of `target` and its children to `outputs`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Route {
+    /// A target such as `app::audit`.
+    pub target: String,
+    /// Output names, `console`, `file`, `otlp` or the name of a named file.
+    pub outputs: Vec<String>,
+    /// Also send the records to the outputs of the parent targets and the root.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub additive: bool,
+}
+
+impl Route {
+    pub fn new<T, I, S>(target: T, outputs: I) -> Route
+    where
+        T: Into<String>,
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Route {
+            target: target.into(),
+            outputs: outputs.into_iter().map(Into::into).collect(),
+            additive: false,
+        }
+    }
+
+    pub fn additive(mut self, additive: bool) -> Route {
+        self.additive = additive;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route_serde() {
+        let toml = r#"
+            target = "hyper"
+            outputs = ["file", "error"]
+        "#;
+        let route: Route = toml::from_str(toml).unwrap();
+        assert_eq!(route, Route::new("hyper", ["file", "error"]));
+
+        let route = route.additive(true);
+        let json = serde_json::to_string(&route).unwrap();
+        assert_eq!(
+            json,
+            r#"{"target":"hyper","outputs":["file","error"],"additive":true}"#
+        );
+    }
+}
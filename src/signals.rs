@@ -0,0 +1,200 @@
+//! Unix signal handling, enabled by the `signals` feature.
+//!
+//! * `SIGHUP` reloads the config file when one is given, and reopens the log files.
+//! * `SIGUSR1` raises the root level one step, e.g. `info` to `debug`.
+//! * `SIGUSR2` lowers the root level one step, e.g. `info` to `warn`.
+
+use crate::config_file::reload_from_path;
+use crate::inner::{modify_log_conf, reopen_files};
+use crate::{SimpleLogError, SimpleResult};
+use log::LevelFilter;
+use signal_hook::consts::{SIGHUP, SIGUSR1, SIGUSR2};
+use signal_hook::flag::register_conditional_default;
+use signal_hook::iterator::{Handle, Signals};
+use signal_hook::low_level::unregister;
+use signal_hook::SigId;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+
+const SIGNALS: [i32; 3] = [SIGHUP, SIGUSR1, SIGUSR2];
+
+/// The default actions registered by the last [handle_signals], armed once it stops.
+static DEFAULT_ACTIONS: Mutex<Vec<SigId>> = Mutex::new(Vec::new());
+
+/// The handle of the [handle_signals] thread. Dropping it restores the default actions,
+/// `SIGHUP`, `SIGUSR1` and `SIGUSR2` terminate the process again.
+pub struct SignalHandler {
+    handle: Handle,
+    thread: Option<JoinHandle<()>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl SignalHandler {
+    /// Stop handling signals and wait for the signal thread to exit.
+    pub fn stop(self) {}
+}
+
+impl Drop for SignalHandler {
+    fn drop(&mut self) {
+        // arm the default actions before our action goes away, so no signal is swallowed
+        self.stopped.store(true, Ordering::SeqCst);
+        self.handle.close();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Move `level` one step towards `Trace` when `up`, towards `Off` otherwise.
+fn step_level(level: LevelFilter, up: bool) -> LevelFilter {
+    let index = level as usize;
+    let index = if up {
+        index + 1
+    } else {
+        index.saturating_sub(1)
+    };
+    LevelFilter::iter().nth(index).unwrap_or(LevelFilter::Trace)
+}
+
+/// Step the root level, keeping the target levels.
+fn step_root_level(up: bool) -> SimpleResult<LevelFilter> {
+    let config = modify_log_conf(|log_config| {
+        log_config.level.0 = step_level(log_config.level.0, up);
+        Ok(())
+    })?;
+    Ok(config.level.0)
+}
+
+/// Run the default action of each signal once `stopped` is set, replacing the actions of
+/// an earlier [handle_signals].
+fn register_default_actions(stopped: &Arc<AtomicBool>) -> SimpleResult<()> {
+    let mut actions = DEFAULT_ACTIONS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    for id in actions.drain(..) {
+        unregister(id);
+    }
+    for signal in SIGNALS {
+        let id = register_conditional_default(signal, stopped.clone())
+            .map_err(SimpleLogError::Signal)?;
+        actions.push(id);
+    }
+    Ok(())
+}
+
+fn reload(config_path: Option<&PathBuf>) -> SimpleResult<()> {
+    match config_path {
+        Some(path) => reload_from_path(path).map(|_| ()),
+        None => reopen_files(),
+    }
+}
+
+/// Install `SIGHUP`, `SIGUSR1` and `SIGUSR2` handlers on a background thread.
+///
+/// On `SIGHUP` the config is reloaded from `config_path` if given, an invalid file
+/// keeps the current config. The log files are reopened either way.
+///
+/// # Examples
+///
+/// ```rust
+/// use signal_hook::consts::{SIGHUP, SIGUSR1, SIGUSR2};
+/// use signal_hook::low_level::raise;
+/// use std::time::Duration;
+///
+/// fn main() -> Result<(), String> {
+///     simple_log::console("info,app=warn")?;
+///     let handler = simple_log::handle_signals(None)?;
+///
+///     raise(SIGUSR1).unwrap();
+///     std::thread::sleep(Duration::from_millis(100));
+///     let config = simple_log::get_log_conf()?;
+///     assert_eq!(config.get_level(), "DEBUG");
+///     assert_eq!(config.level.1.len(), 1);
+///
+///     raise(SIGUSR2).unwrap();
+///     std::thread::sleep(Duration::from_millis(100));
+///     raise(SIGUSR2).unwrap();
+///     std::thread::sleep(Duration::from_millis(100));
+///     assert_eq!(simple_log::get_log_conf()?.get_level(), "WARN");
+///
+///     // reopen log files, the current config is kept
+///     raise(SIGHUP).unwrap();
+///     std::thread::sleep(Duration::from_millis(100));
+///     assert_eq!(simple_log::get_log_conf()?.get_level(), "WARN");
+///
+///     // handle the signals again after a stop
+///     handler.stop();
+///     let _handler = simple_log::handle_signals(None)?;
+///     raise(SIGUSR1).unwrap();
+///     std::thread::sleep(Duration::from_millis(100));
+///     assert_eq!(simple_log::get_log_conf()?.get_level(), "INFO");
+///     Ok(())
+/// }
+/// ```
+pub fn handle_signals(config_path: Option<PathBuf>) -> SimpleResult<SignalHandler> {
+    let stopped = Arc::new(AtomicBool::new(false));
+    register_default_actions(&stopped)?;
+    let mut signals = Signals::new(SIGNALS).map_err(SimpleLogError::Signal)?;
+    let handle = signals.handle();
+    let thread = thread::Builder::new()
+        .name("simple_log_signals".to_string())
+        .spawn(move || {
+            for signal in signals.forever() {
+                let result = match signal {
+                    SIGHUP => reload(config_path.as_ref()),
+                    SIGUSR1 => step_root_level(true).map(|_| ()),
+                    SIGUSR2 => step_root_level(false).map(|_| ()),
+                    _ => Ok(()),
+                };
+                if let Err(e) = result {
+                    log::error!(target: "simple_log", "Failed to handle signal {}: {}", signal, e);
+                }
+            }
+        })
+        .map_err(SimpleLogError::Signal)?;
+    Ok(SignalHandler {
+        handle,
+        thread: Some(thread),
+        stopped,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_level() {
+        assert_eq!(step_level(LevelFilter::Info, true), LevelFilter::Debug);
+        assert_eq!(step_level(LevelFilter::Trace, true), LevelFilter::Trace);
+        assert_eq!(step_level(LevelFilter::Info, false), LevelFilter::Warn);
+        assert_eq!(step_level(LevelFilter::Off, false), LevelFilter::Off);
+        assert_eq!(step_level(LevelFilter::Off, true), LevelFilter::Error);
+    }
+
+    #[test]
+    fn test_stop_restores_default_actions() {
+        use std::os::unix::process::ExitStatusExt;
+        use std::process::Command;
+
+        const CHILD: &str = "SIMPLE_LOG_TEST_SIGNAL_CHILD";
+        if std::env::var_os(CHILD).is_some() {
+            handle_signals(None).unwrap().stop();
+            signal_hook::low_level::raise(SIGHUP).unwrap();
+            std::thread::sleep(std::time::Duration::from_secs(5));
+            std::process::exit(0);
+        }
+        // run this test alone in a child process, SIGHUP must terminate it
+        let status = Command::new(std::env::current_exe().unwrap())
+            .args([
+                "signals::tests::test_stop_restores_default_actions",
+                "--exact",
+            ])
+            .env(CHILD, "1")
+            .status()
+            .unwrap();
+        assert_eq!(status.signal(), Some(SIGHUP));
+    }
+}
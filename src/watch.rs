@@ -0,0 +1,169 @@
+//! Reload [LogConfig] when its config file changes.
+
+use crate::config_file::reload_from_path;
+use crate::{is_initialized, LogConfig, SimpleLogError, SimpleResult};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Tracks the last seen content of the watched file.
+struct ConfigPoller {
+    path: PathBuf,
+    last: String,
+    /// The last read failed, used to log a missing file once.
+    unreadable: bool,
+}
+
+impl ConfigPoller {
+    fn new(path: PathBuf) -> SimpleResult<ConfigPoller> {
+        let last = read_to_string(&path)?;
+        Ok(ConfigPoller {
+            path,
+            last,
+            unreadable: false,
+        })
+    }
+
+    /// Returns `true` when the file content differs from the last poll.
+    fn changed(&mut self) -> SimpleResult<bool> {
+        let content = read_to_string(&self.path).inspect_err(|_| self.unreadable = true)?;
+        self.unreadable = false;
+        if content == self.last {
+            return Ok(false);
+        }
+        self.last = content;
+        Ok(true)
+    }
+
+    fn reload(&self) -> SimpleResult<LogConfig> {
+        reload_from_path(&self.path)
+    }
+}
+
+fn read_to_string(path: &Path) -> SimpleResult<String> {
+    std::fs::read_to_string(path).map_err(|source| SimpleLogError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// The handle of a [watch] thread. Dropping it stops watching.
+pub struct ConfigWatcher {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ConfigWatcher {
+    /// Stop watching and wait for the watch thread to exit.
+    pub fn stop(self) {}
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Poll a TOML, YAML or JSON config file every `interval` and apply it when it changes.
+///
+/// A valid config is applied through [update_log_conf]. An invalid one keeps the current
+/// config and the error is logged, so the file can be fixed and saved again.
+///
+/// simple-log must be initialized first, and the returned [ConfigWatcher] must be kept alive.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(feature = "json")]
+/// # fn main() -> Result<(), String> {
+/// use std::time::Duration;
+///
+/// let path = std::env::temp_dir().join("simple_log_watch_doc.json");
+/// std::fs::write(&path, r#"{"level":"debug","out_kind":"console"}"#).unwrap();
+/// simple_log::from_file(&path)?;
+/// let _watcher = simple_log::watch(&path, Duration::from_millis(20))?;
+///
+/// std::fs::write(&path, r#"{"level":"warn","out_kind":"console"}"#).unwrap();
+/// std::thread::sleep(Duration::from_millis(200));
+/// assert_eq!(simple_log::get_log_conf()?.get_level(), "WARN");
+///
+/// // invalid config is logged and ignored
+/// std::fs::write(&path, r#"{"level":"warning"}"#).unwrap();
+/// std::thread::sleep(Duration::from_millis(200));
+/// assert_eq!(simple_log::get_log_conf()?.get_level(), "WARN");
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "json"))]
+/// # fn main() {}
+/// ```
+pub fn watch<P: Into<PathBuf>>(path: P, interval: Duration) -> SimpleResult<ConfigWatcher> {
+    if !is_initialized() {
+        return Err(SimpleLogError::NotInitialized);
+    }
+    let mut poller = ConfigPoller::new(path.into())?;
+    let (stop, stopped) = mpsc::channel::<()>();
+    let name = "simple_log_watch".to_string();
+    let thread = thread::Builder::new()
+        .name(name.clone())
+        .spawn(move || loop {
+            match stopped.recv_timeout(interval) {
+                Err(RecvTimeoutError::Timeout) => {}
+                _ => return,
+            }
+            let unreadable = poller.unreadable;
+            match poller.changed() {
+                Ok(false) => {}
+                Ok(true) => match poller.reload() {
+                    Ok(_) => log::info!(
+                        target: "simple_log",
+                        "Reloaded log config from {}",
+                        poller.path.display()
+                    ),
+                    Err(e) => log::error!(
+                        target: "simple_log",
+                        "Keep current log config, reload failed: {}",
+                        e
+                    ),
+                },
+                Err(e) if !unreadable => log::warn!(target: "simple_log", "{}", e),
+                Err(_) => {}
+            }
+        })
+        .map_err(|source| SimpleLogError::Thread { name, source })?;
+    Ok(ConfigWatcher {
+        stop: Some(stop),
+        thread: Some(thread),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poller_changed() {
+        let path = std::env::temp_dir().join("simple_log_poller_changed.toml");
+        std::fs::write(&path, "level = \"debug\"").unwrap();
+        let mut poller = ConfigPoller::new(path.clone()).unwrap();
+        assert!(!poller.changed().unwrap());
+
+        std::fs::write(&path, "level = \"info\"").unwrap();
+        assert!(poller.changed().unwrap());
+        assert!(!poller.changed().unwrap());
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(poller.changed(), Err(SimpleLogError::Io { .. })));
+    }
+
+    #[test]
+    fn test_watch_not_initialized() {
+        let err = watch("not_exist.toml", Duration::from_secs(1))
+            .err()
+            .unwrap();
+        assert!(matches!(err, SimpleLogError::NotInitialized));
+    }
+}